@@ -0,0 +1,272 @@
+#!/usr/bin/env python3
+# 生成截图测试用的字体 MybarTestMono.ttf：
+# DejaVu Sans Mono 加上组件用到的图标码位，每个图标画成一个方框，框里用 3x4 的小方块
+# 表示码位的低 12 位。这样参考图里不会出现缺字的方框，换了图标也能在差异图里看出来。
+#
+# 用法：python3 fixtures/fonts/build.py /usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf
+# 现在的文件由 DejaVu Sans Mono 2.37 生成（Debian fonts-dejavu-core，sha256 0f5db4f1…），
+# 同一个输入总是生成同样的文件。
+# 组件换了或者加了图标时，把码位加到 ICONS 里重新生成，再用 MYBAR_UPDATE_GOLDEN=1 更新参考图。
+import struct
+import sys
+from pathlib import Path
+
+FAMILY = "Mybar Test Mono"
+OUTPUT = Path(__file__).with_name("MybarTestMono.ttf")
+
+ICONS = [
+    # battery
+    0xF0E7, 0xF240, 0xF241, 0xF242, 0xF243, 0xF244,
+    # cpu / disk / light
+    0xF2DB, 0xF0A0, 0xF185,
+    # icons / rewrite
+    0xF07B, 0xF120, 0xF121, 0xF268, 0xF269, 0xF2C6, 0xF2D0,
+    # memory
+    0xF0EC, 0xF035B,
+    # network
+    0xF023, 0xF127, 0xF1EB, 0xF0200,
+    # temperature
+    0xF2C7, 0xF2C8, 0xF2C9, 0xF2CA, 0xF2CB, 0xF0210,
+    # volume
+    0xF026, 0xF027,
+    # main: tail / uptime
+    0xF04B, 0xF04C, 0xF108,
+]
+
+# 图标在 1233 x 2048 的字宽里的位置
+FRAME = (120, -80, 1113, 1420)
+BORDER = 110
+COLUMNS, ROWS, GAP = 3, 4, 70
+
+# 字形编号变了这些表就不对了，测试用不到，直接去掉
+DROP = {b"hdmx", b"LTSH", b"VDMX", b"DSIG"}
+
+
+def rect(x0, y0, x1, y1, clockwise=True):
+    points = [(x0, y0), (x0, y1), (x1, y1), (x1, y0)]
+    return points if clockwise else points[::-1]
+
+
+def icon_contours(codepoint):
+    x0, y0, x1, y1 = FRAME
+    contours = [rect(x0, y0, x1, y1), rect(x0 + BORDER, y0 + BORDER, x1 - BORDER, y1 - BORDER, False)]
+    ix0, iy0 = x0 + BORDER + GAP, y0 + BORDER + GAP
+    width = (x1 - x0 - 2 * BORDER - (COLUMNS + 1) * GAP) // COLUMNS
+    height = (y1 - y0 - 2 * BORDER - (ROWS + 1) * GAP) // ROWS
+    for bit in range(COLUMNS * ROWS):
+        if not codepoint >> bit & 1:
+            continue
+        column, row = bit % COLUMNS, bit // COLUMNS
+        cx = ix0 + column * (width + GAP)
+        # 最低位在左上角
+        cy = iy0 + (ROWS - 1 - row) * (height + GAP)
+        contours.append(rect(cx, cy, cx + width, cy + height))
+    return contours
+
+
+def encode_glyph(contours):
+    points = [p for c in contours for p in c]
+    xs, ys = [p[0] for p in points], [p[1] for p in points]
+    data = struct.pack(">hhhhh", len(contours), min(xs), min(ys), max(xs), max(ys))
+    end = -1
+    for c in contours:
+        end += len(c)
+        data += struct.pack(">H", end)
+    data += struct.pack(">H", 0)  # 没有指令
+    data += bytes([1]) * len(points)  # 都是线段端点，坐标用 int16 的差值
+    for axis in (0, 1):
+        previous = 0
+        for p in points:
+            data += struct.pack(">h", p[axis] - previous)
+            previous = p[axis]
+    return data, len(points), min(xs)
+
+
+def read_tables(font):
+    _, count = struct.unpack(">IH", font[:6])
+    tables = {}
+    for i in range(count):
+        tag, _, offset, length = struct.unpack(">4sIII", font[12 + 16 * i : 28 + 16 * i])
+        tables[tag] = font[offset : offset + length]
+    return tables
+
+
+def read_cmap(cmap):
+    # 只读 (3, 1) 的 format 4
+    _, count = struct.unpack(">HH", cmap[:4])
+    for i in range(count):
+        platform, encoding, offset = struct.unpack(">HHI", cmap[4 + 8 * i : 12 + 8 * i])
+        if (platform, encoding) == (3, 1):
+            break
+    else:
+        sys.exit("no (3, 1) cmap")
+    table = cmap[offset:]
+    assert struct.unpack(">H", table[:2])[0] == 4
+    segments = struct.unpack(">H", table[6:8])[0] // 2
+    arrays = 14
+    end = struct.unpack(f">{segments}H", table[arrays : arrays + 2 * segments])
+    arrays += 2 * segments + 2
+    start = struct.unpack(f">{segments}H", table[arrays : arrays + 2 * segments])
+    arrays += 2 * segments
+    delta = struct.unpack(f">{segments}h", table[arrays : arrays + 2 * segments])
+    arrays += 2 * segments
+    range_offsets = arrays
+    mapping = {}
+    for s in range(segments):
+        range_offset = struct.unpack(">H", table[range_offsets + 2 * s : range_offsets + 2 * s + 2])[0]
+        for code in range(start[s], end[s] + 1):
+            if code == 0xFFFF:
+                continue
+            if range_offset == 0:
+                glyph = (code + delta[s]) & 0xFFFF
+            else:
+                at = range_offsets + 2 * s + range_offset + 2 * (code - start[s])
+                glyph = struct.unpack(">H", table[at : at + 2])[0]
+                if glyph:
+                    glyph = (glyph + delta[s]) & 0xFFFF
+            if glyph:
+                mapping[code] = glyph
+    return mapping
+
+
+def runs(mapping):
+    # 码位和字形编号都连续的一段
+    result = []
+    for code in sorted(mapping):
+        glyph = mapping[code]
+        if result and code == result[-1][1] + 1 and glyph == result[-1][2] + code - result[-1][0]:
+            result[-1][1] = code
+        else:
+            result.append([code, code, glyph])
+    return result
+
+
+def format4(mapping):
+    segments = runs({c: g for c, g in mapping.items() if c < 0xFFFF})
+    segments.append([0xFFFF, 0xFFFF, 0])
+    count = len(segments)
+    search = 2 ** (count.bit_length() - 1)
+    body = struct.pack(">HHHH", count * 2, search * 2, search.bit_length() - 1, 2 * count - 2 * search)
+    body += struct.pack(f">{count}H", *[s[1] for s in segments]) + b"\0\0"
+    body += struct.pack(f">{count}H", *[s[0] for s in segments])
+    body += struct.pack(f">{count}H", *[(s[2] - s[0]) & 0xFFFF for s in segments])
+    body += struct.pack(f">{count}H", *[0] * count)
+    return struct.pack(">HHH", 4, 6 + len(body), 0) + body
+
+
+def format12(mapping):
+    groups = runs(mapping)
+    body = b"".join(struct.pack(">III", *g) for g in groups)
+    return struct.pack(">HHIII", 12, 0, 16 + len(body), 0, len(groups)) + body
+
+
+def build_cmap(mapping):
+    subtables = [(3, 1, format4(mapping)), (3, 10, format12(mapping))]
+    data = struct.pack(">HH", 0, len(subtables))
+    offset = 4 + 8 * len(subtables)
+    for platform, encoding, table in subtables:
+        data += struct.pack(">HHI", platform, encoding, offset)
+        offset += len(table)
+    return data + b"".join(t for _, _, t in subtables)
+
+
+def read_names(name):
+    _, count, storage = struct.unpack(">HHH", name[:6])
+    names = {}
+    for i in range(count):
+        platform, encoding, _, name_id, length, offset = struct.unpack(">6H", name[6 + 12 * i : 18 + 12 * i])
+        if (platform, encoding) == (3, 1):
+            names[name_id] = name[storage + offset : storage + offset + length].decode("utf-16-be")
+    return names
+
+
+def build_name(original):
+    names = {
+        0: original[0],
+        1: FAMILY,
+        2: "Book",
+        3: f"{FAMILY} Book",
+        4: f"{FAMILY} Book",
+        5: original[5],
+        6: FAMILY.replace(" ", ""),
+        13: original[13],
+    }
+    records, storage = b"", b""
+    for name_id, text in sorted(names.items()):
+        encoded = text.encode("utf-16-be")
+        records += struct.pack(">6H", 3, 1, 0x409, name_id, len(encoded), len(storage))
+        storage += encoded
+    return struct.pack(">HHH", 0, len(names), 6 + len(records)) + records + storage
+
+
+def checksum(data):
+    data += b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(f">{len(data) // 4}I", data)) & 0xFFFFFFFF
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit(f"usage: {sys.argv[0]} DejaVuSansMono.ttf")
+    tables = read_tables(Path(sys.argv[1]).read_bytes())
+
+    head = bytearray(tables[b"head"])
+    long_loca = struct.unpack(">h", head[50:52])[0] == 1
+    glyph_count = struct.unpack(">H", tables[b"maxp"][4:6])[0]
+    loca = tables[b"loca"]
+    if long_loca:
+        offsets = list(struct.unpack(f">{glyph_count + 1}I", loca))
+    else:
+        offsets = [o * 2 for o in struct.unpack(f">{glyph_count + 1}H", loca)]
+
+    glyf = bytearray(tables[b"glyf"][: offsets[-1]])
+    hmtx = bytearray(tables[b"hmtx"])
+    mapping = read_cmap(tables[b"cmap"])
+    max_points = max_contours = 0
+    for codepoint in ICONS:
+        data, points, xmin = encode_glyph(icon_contours(codepoint))
+        contours = len(icon_contours(codepoint))
+        max_points, max_contours = max(max_points, points), max(max_contours, contours)
+        glyf += data + b"\0" * (-len(data) % 4)
+        offsets.append(len(glyf))
+        # 等宽字体的 hmtx 后面只有 lsb，新字形沿用最后一个字宽
+        hmtx += struct.pack(">h", xmin)
+        mapping[codepoint] = glyph_count
+        glyph_count += 1
+
+    maxp = bytearray(tables[b"maxp"])
+    struct.pack_into(">H", maxp, 4, glyph_count)
+    struct.pack_into(">H", maxp, 6, max(max_points, struct.unpack(">H", maxp[6:8])[0]))
+    struct.pack_into(">H", maxp, 8, max(max_contours, struct.unpack(">H", maxp[8:10])[0]))
+    struct.pack_into(">h", head, 50, 1)
+    struct.pack_into(">I", head, 8, 0)
+
+    tables[b"head"] = bytes(head)
+    tables[b"maxp"] = bytes(maxp)
+    tables[b"glyf"] = bytes(glyf)
+    tables[b"loca"] = struct.pack(f">{len(offsets)}I", *offsets)
+    tables[b"hmtx"] = bytes(hmtx)
+    tables[b"cmap"] = build_cmap(mapping)
+    tables[b"name"] = build_name(read_names(tables[b"name"]))
+    # format 3 不带字形名字
+    tables[b"post"] = struct.pack(">I", 0x00030000) + tables[b"post"][4:32]
+    for tag in DROP:
+        tables.pop(tag, None)
+
+    tags = sorted(tables)
+    search = 2 ** (len(tags).bit_length() - 1)
+    font = struct.pack(">IHHHH", 0x00010000, len(tags), search * 16, search.bit_length() - 1, len(tags) * 16 - search * 16)
+    offset = len(font) + 16 * len(tags)
+    directory, body = b"", b""
+    for tag in tags:
+        data = tables[tag]
+        if tag == b"head":
+            head_offset = offset + len(body)
+        directory += struct.pack(">4sIII", tag, checksum(data), offset + len(body), len(data))
+        body += data + b"\0" * (-len(data) % 4)
+    font = bytearray(font + directory + body)
+    struct.pack_into(">I", font, head_offset + 8, (0xB1B0AFBA - checksum(bytes(font))) & 0xFFFFFFFF)
+    OUTPUT.write_bytes(font)
+
+
+if __name__ == "__main__":
+    main()
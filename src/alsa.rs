@@ -1,3 +1,14 @@
+use std::cell::Cell;
+
+/// Volume 组件用到的音量控制，无界面渲染和测试时用 FixedMixer 代替 ALSA
+pub trait Mixer {
+    /// 0.0 - 1.0
+    fn get_current_volume(&self) -> f64;
+    fn is_unmuted(&self) -> bool;
+    fn set_current_volumn(&self, v: f64);
+    fn toggle_mute(&self);
+}
+
 pub struct Audio {
     pub min: i64,
     pub max: i64,
@@ -30,28 +41,31 @@ impl Audio {
     fn get_selem(&self) -> alsa::mixer::Selem {
         self.mixer.find_selem(&self.sid).unwrap()
     }
-    pub fn get_current_volume(&self) -> f64 {
+}
+
+impl Mixer for Audio {
+    fn get_current_volume(&self) -> f64 {
         let selem = self.get_selem();
         let v = selem
             .get_playback_volume(alsa::mixer::SelemChannelId::FrontLeft)
             .unwrap();
         v as f64 / (self.max - self.min) as f64
     }
-    pub fn is_unmuted(&self) -> bool {
+    fn is_unmuted(&self) -> bool {
         let selem = self.get_selem();
         selem
             .get_playback_switch(alsa::mixer::SelemChannelId::FrontLeft)
             .unwrap()
             == 1
     }
-    pub fn set_current_volumn(&self, v: f64) {
+    fn set_current_volumn(&self, v: f64) {
         let mut v = v.max(0.);
         v = v.min(1.);
         let v: i64 = (v * (self.max - self.min) as f64).floor() as i64;
         let selem = self.get_selem();
         selem.set_playback_volume_all(v).unwrap();
     }
-    pub fn toggle_mute(&self) {
+    fn toggle_mute(&self) {
         let selem = self.get_selem();
         selem
             .set_playback_switch_all(match self.is_unmuted() {
@@ -68,9 +82,39 @@ impl Default for Audio {
     }
 }
 
+/// 不接触声卡的音量，调节只改变内存里的值
+pub struct FixedMixer {
+    volume: Cell<f64>,
+    unmuted: Cell<bool>,
+}
+
+impl FixedMixer {
+    pub fn new(volume: f64, unmuted: bool) -> Self {
+        Self {
+            volume: Cell::new(volume),
+            unmuted: Cell::new(unmuted),
+        }
+    }
+}
+
+impl Mixer for FixedMixer {
+    fn get_current_volume(&self) -> f64 {
+        self.volume.get()
+    }
+    fn is_unmuted(&self) -> bool {
+        self.unmuted.get()
+    }
+    fn set_current_volumn(&self, v: f64) {
+        self.volume.set(v.clamp(0.0, 1.0));
+    }
+    fn toggle_mute(&self) {
+        self.unmuted.set(!self.unmuted.get());
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::Audio;
+    use super::{Audio, Mixer};
 
     // #[test]
     fn test01() {
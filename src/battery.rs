@@ -0,0 +1,281 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::MyResult;
+
+const SYSFS_ROOT: &str = "/sys/class/power_supply";
+
+/// 电池的充放电状态，对应 sysfs 中 status 文件的取值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Full,
+    NotCharging,
+    Unknown,
+}
+
+impl BatteryState {
+    fn parse(status: &str) -> Self {
+        match status {
+            "Charging" => BatteryState::Charging,
+            "Discharging" => BatteryState::Discharging,
+            "Full" => BatteryState::Full,
+            "Not charging" => BatteryState::NotCharging,
+            _ => BatteryState::Unknown,
+        }
+    }
+
+    // 多块电池状态不同时按这个优先级合并
+    fn priority(self) -> u8 {
+        match self {
+            BatteryState::Charging => 4,
+            BatteryState::Discharging => 3,
+            BatteryState::NotCharging => 2,
+            BatteryState::Full => 1,
+            BatteryState::Unknown => 0,
+        }
+    }
+}
+
+/// 所有电池合并后的状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatteryStatus {
+    pub percent: u8,
+    pub state: BatteryState,
+    /// 放电时是剩余时间，充电时是充满所需时间
+    pub time_left: Option<Duration>,
+    pub ac_online: bool,
+}
+
+// 读数的单位：µWh 和 µW，或者没有电压无法换算时的 µAh 和 µA
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Energy,
+    Charge,
+}
+
+// 一块电池的原始读数；只提供 charge_* 和 current_now 的电池用电压换算成能量，
+// 这样才能和提供 energy_* 的电池相加
+struct Reading {
+    state: BatteryState,
+    unit: Unit,
+    capacity: Option<u64>,
+    now: Option<u64>,
+    full: Option<u64>,
+    rate: Option<u64>,
+}
+
+/// /sys/class/power_supply 下的电池和交流电源
+pub struct PowerSupply {
+    root: PathBuf,
+}
+
+impl Default for PowerSupply {
+    fn default() -> Self {
+        Self::new(SYSFS_ROOT)
+    }
+}
+
+fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_number(path: &Path) -> Option<u64> {
+    read_string(path)?.parse().ok()
+}
+
+impl PowerSupply {
+    /// root 一般是 /sys/class/power_supply，测试时可以指向一个假的目录
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 读取所有电池，没有电池时返回 None
+    pub fn read(&self) -> MyResult<Option<BatteryStatus>> {
+        let mut readings = vec![];
+        let mut ac_online = false;
+        let mut entries: Vec<PathBuf> = fs::read_dir(&self.root)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect();
+        entries.sort();
+        for dir in entries {
+            match read_string(&dir.join("type")).as_deref() {
+                Some("Battery") => {
+                    // 可拆卸电池槽位为空时 present 为 0
+                    if read_number(&dir.join("present")) == Some(0) {
+                        continue;
+                    }
+                    readings.push(read_battery(&dir));
+                }
+                Some("Mains") | Some("USB") => {
+                    ac_online |= read_number(&dir.join("online")) == Some(1);
+                }
+                _ => {}
+            }
+        }
+        Ok(combine(&readings, ac_online))
+    }
+}
+
+fn read_battery(dir: &Path) -> Reading {
+    let number = |name: &str| read_number(&dir.join(name));
+    // energy_full 一般就是 charge_full 乘以标称电压，没有标称电压时用当前电压
+    let voltage = number("voltage_min_design").or(number("voltage_now"));
+    // µAh、µA 乘以 µV 再除以 10^6 得到 µWh、µW
+    let to_energy = |charge: Option<u64>| Some(charge? * voltage? / 1_000_000);
+    let energy = (number("energy_now"), number("energy_full"));
+    let charge = (number("charge_now"), number("charge_full"));
+    let (unit, (now, full)) = if energy.0.is_some() || energy.1.is_some() {
+        (Unit::Energy, energy)
+    } else if voltage.is_some() {
+        (Unit::Energy, (to_energy(charge.0), to_energy(charge.1)))
+    } else {
+        (Unit::Charge, charge)
+    };
+    let rate = match unit {
+        Unit::Energy => number("power_now").or(to_energy(number("current_now"))),
+        Unit::Charge => number("current_now"),
+    };
+    Reading {
+        state: BatteryState::parse(&read_string(&dir.join("status")).unwrap_or_default()),
+        unit,
+        capacity: number("capacity"),
+        now,
+        full,
+        rate,
+    }
+}
+
+fn combine(readings: &[Reading], ac_online: bool) -> Option<BatteryStatus> {
+    if readings.is_empty() {
+        return None;
+    }
+    let state = readings
+        .iter()
+        .map(|r| r.state)
+        .max_by_key(|s| s.priority())
+        .unwrap_or(BatteryState::Unknown);
+    // 单位不同的读数不能相加，这时退回到 capacity，也不估计剩余时间
+    let same_unit = readings.iter().all(|r| r.unit == readings[0].unit);
+    let (now, full, rate): (Option<u64>, Option<u64>, u64) = if same_unit {
+        (
+            readings.iter().map(|r| r.now).sum(),
+            readings.iter().map(|r| r.full).sum(),
+            readings.iter().filter_map(|r| r.rate).sum(),
+        )
+    } else {
+        (None, None, 0)
+    };
+
+    let percent = match (now, full) {
+        (Some(now), Some(full)) if full > 0 => now * 100 / full,
+        // 没有能量读数时取各电池 capacity 的平均值
+        _ => {
+            let capacities: Vec<u64> = readings.iter().filter_map(|r| r.capacity).collect();
+            capacities.iter().sum::<u64>() / capacities.len().max(1) as u64
+        }
+    };
+
+    let time_left = match (state, now, full) {
+        _ if rate == 0 => None,
+        (BatteryState::Discharging, Some(now), _) => Some(now as f64 / rate as f64),
+        (BatteryState::Charging, Some(now), Some(full)) => {
+            Some(full.saturating_sub(now) as f64 / rate as f64)
+        }
+        _ => None,
+    }
+    .map(|hours| Duration::from_secs_f64(hours * 3600.0));
+
+    Some(BatteryStatus {
+        percent: percent.min(100) as u8,
+        state,
+        time_left,
+        ac_online,
+    })
+}
+
+/// 把剩余时间格式化成 "H:MM"
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::{BatteryState, PowerSupply, format_duration};
+
+    fn write(root: &std::path::Path, supply: &str, files: &[(&str, &str)]) {
+        let dir = root.join(supply);
+        fs::create_dir_all(&dir).unwrap();
+        for (name, value) in files {
+            fs::write(dir.join(name), format!("{value}\n")).unwrap();
+        }
+    }
+
+    #[test]
+    fn read_discharging_battery() {
+        let root = std::env::temp_dir().join(format!("mybar-battery-{}", std::process::id()));
+        write(&root, "AC", &[("type", "Mains"), ("online", "0")]);
+        write(
+            &root,
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "49"),
+                ("energy_now", "25000000"),
+                ("energy_full", "50000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+        let status = PowerSupply::new(&root).read().unwrap().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(status.percent, 50);
+        assert_eq!(status.state, BatteryState::Discharging);
+        assert_eq!(status.time_left, Some(Duration::from_secs(9000)));
+        assert!(!status.ac_online);
+        assert_eq!(format_duration(status.time_left.unwrap()), "2:30");
+    }
+
+    #[test]
+    fn combine_energy_and_charge_batteries() {
+        let root = std::env::temp_dir().join(format!("mybar-battery-mixed-{}", std::process::id()));
+        write(
+            &root,
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("energy_now", "25000000"),
+                ("energy_full", "50000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+        // 2 Ah、10 V，相当于 20 Wh；0.5 A 相当于 5 W
+        write(
+            &root,
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("charge_now", "2000000"),
+                ("charge_full", "2000000"),
+                ("current_now", "500000"),
+                ("voltage_min_design", "10000000"),
+            ],
+        );
+        let status = PowerSupply::new(&root).read().unwrap().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        // (25 + 20) / (50 + 20)
+        assert_eq!(status.percent, 64);
+        // 45 Wh / 15 W
+        assert_eq!(status.time_left, Some(Duration::from_secs(3 * 3600)));
+    }
+}
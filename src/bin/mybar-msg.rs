@@ -0,0 +1,63 @@
+// 和状态栏共用协议，其中只在状态栏一侧用到的部分这里用不到
+#[path = "../ipc/protocol.rs"]
+#[allow(dead_code)]
+mod protocol;
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::process::ExitCode;
+
+use protocol::{Reply, Request};
+
+const USAGE: &str = "usage: mybar-msg hide | show | toggle | reload | update <module> \
+                     | action <module> <name> | subscribe [desktop|volume|update|click]... | state";
+
+/// 给正在运行的状态栏发送一条命令，例如在 sxhkd 里绑定 `mybar-msg toggle`
+///
+/// subscribe 会一直运行，把收到的事件每行一个 JSON 输出到 stdout；state 输出所有组件的 JSON。
+fn main() -> ExitCode {
+    let line = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    let request = match Request::parse(&line) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match send(&request) {
+        Ok(reply) if reply.success => ExitCode::SUCCESS,
+        Ok(reply) => {
+            eprintln!("{}", reply.error.unwrap_or_default());
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("{}: {e}", protocol::socket_path().display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn send(request: &Request) -> std::io::Result<Reply> {
+    let mut stream = UnixStream::connect(protocol::socket_path())?;
+    writeln!(stream, "{request}")?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let reply: Reply = serde_json::from_str(&line).map_err(std::io::Error::other)?;
+    if !reply.success {
+        return Ok(reply);
+    }
+    if let Some(data) = &reply.data {
+        let data = serde_json::to_string_pretty(data).map_err(std::io::Error::other)?;
+        println!("{data}");
+    }
+    if let Request::Subscribe(_) = request {
+        // 状态栏退出时连接关闭，这里随之结束
+        let mut stdout = std::io::stdout().lock();
+        for line in reader.lines() {
+            writeln!(stdout, "{}", line?)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(reply)
+}
@@ -73,6 +73,13 @@ impl Bspwm {
         bspwm
     }
 
+    /// 用一行 report（例如 "WMeDP-1:O1:o2:u3:LT:TT:G"）构造，不连接 bspwm，用于无界面渲染和测试
+    pub fn from_report(report: &str) -> Arc<Mutex<Bspwm>> {
+        let mut bspwm = Bspwm { monitors: vec![] };
+        bspwm.parse_report(report);
+        Arc::new(Mutex::new(bspwm))
+    }
+
     fn parse_report(&mut self, report: &str) {
         let mut cur_monitor: Option<&mut Monitor> = None;
         let mut i = 0;
@@ -0,0 +1,175 @@
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{MyBarError, MyResult};
+
+// 等待子进程结束时轮询的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+// 进程组结束后最多再等这么久读完 stdout
+const READ_GRACE: Duration = Duration::from_millis(100);
+
+/// 命令的运行结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandOutput {
+    pub stdout: String,
+    /// 退出码，被信号杀死（包括超时）时为 None
+    pub code: Option<i32>,
+    pub timed_out: bool,
+}
+
+impl CommandOutput {
+    /// stdout 的第一行
+    pub fn first_line(&self) -> &str {
+        self.stdout.lines().next().unwrap_or_default()
+    }
+}
+
+/// 用 sh -c 运行命令，超过 timeout 时杀掉它的整个进程组
+///
+/// 会阻塞到命令结束，不要在 X 线程上调用。
+pub fn run(command: &str, timeout: Duration) -> MyResult<CommandOutput> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| MyBarError::Other("no stdout pipe".to_string()))?;
+    // 在另一个线程里读，避免输出填满管道后子进程阻塞、永远不退出。
+    // 读到的内容随时放进 output，等不到 EOF 时也能拿到已经输出的部分
+    let output = Arc::new(Mutex::new(vec![]));
+    let (done, finished) = mpsc::channel::<()>();
+    {
+        let output = Arc::clone(&output);
+        std::thread::spawn(move || {
+            let mut chunk = [0; 4096];
+            while let Ok(n @ 1..) = stdout.read(&mut chunk) {
+                if let Ok(mut output) = output.lock() {
+                    output.extend_from_slice(&chunk[..n]);
+                }
+            }
+            let _ = done.send(());
+        });
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            timed_out = true;
+            kill_group(&child);
+            break child.wait()?;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    // sh 放到后台的子进程继承了 stdout，不结束它们读取线程就等不到 EOF
+    kill_group(&child);
+    // 脱离了进程组的子进程仍然可能持有管道，只等一小段时间
+    let _ = finished.recv_timeout(READ_GRACE);
+    let stdout = match output.lock() {
+        Ok(buf) if !timed_out => String::from_utf8_lossy(&buf).into_owned(),
+        _ => String::new(),
+    };
+    Ok(CommandOutput {
+        stdout,
+        code: status.code(),
+        timed_out,
+    })
+}
+
+// 和 terminate 一样杀掉整个进程组，否则 sh 启动的子进程会留下来继续运行；
+// 进程组可能已经没有进程了，kill 失败不影响结果
+fn kill_group(child: &Child) {
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+/// 命令里程序的名字，例如 "/usr/bin/uptime -p" 是 "uptime"，用作组件的默认名字
+pub fn program_name(command: &str) -> String {
+    let program = command.split_whitespace().next().unwrap_or_default();
+    program.rsplit('/').next().unwrap_or(program).to_string()
+}
+
+/// 启动一个长期运行、持续输出的命令，stdout 通过管道读取，stdin 由调用者决定
+///
+/// 命令在自己的进程组里运行，状态栏退出时 [`terminate`] 能连同 sh 启动的子进程一起结束；
+/// 状态栏被信号杀死时，内核会通过 PR_SET_PDEATHSIG 给它发 SIGTERM。
+pub fn spawn_streaming(command: &str, stdin: Stdio) -> MyResult<Child> {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0);
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(cmd.spawn()?)
+}
+
+/// 给 [`spawn_streaming`] 启动的整个进程组发 SIGTERM 并回收
+pub fn terminate(child: &mut Child) {
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM);
+    }
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{program_name, run};
+
+    #[test]
+    fn run_with_timeout() {
+        let output = run("printf 'first\\nsecond\\n'; exit 3", Duration::from_secs(5)).unwrap();
+        assert_eq!(output.first_line(), "first");
+        assert_eq!(output.code, Some(3));
+        assert!(!output.timed_out);
+
+        let output = run("sleep 5", Duration::from_millis(100)).unwrap();
+        assert!(output.timed_out);
+        assert_eq!(output.code, None);
+
+        assert_eq!(program_name("/usr/bin/uptime -p"), "uptime");
+    }
+
+    #[test]
+    fn timeout_kills_process_group() {
+        let marker = std::env::temp_dir().join(format!("mybar-run-{}", std::process::id()));
+        let command = format!("(sleep 0.5; touch {}) & wait", marker.display());
+        let output = run(&command, Duration::from_millis(100)).unwrap();
+        assert!(output.timed_out);
+        std::thread::sleep(Duration::from_secs(1));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn background_child_does_not_block_output() {
+        let start = std::time::Instant::now();
+        let output = run("echo done; sleep 5 &", Duration::from_secs(5)).unwrap();
+        assert_eq!(output.first_line(), "done");
+        assert_eq!(output.code, Some(0));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
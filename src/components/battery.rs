@@ -0,0 +1,169 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
+use super::{Component, Event, Painter, Snapshot};
+use crate::battery::{self, BatteryState, BatteryStatus, PowerSupply};
+use crate::error::MyBarError;
+use crate::message::Message;
+use crate::scheduler::Scheduler;
+use serde_json::json;
+use xcb::x;
+
+// 按电量从低到高的图标
+const RAMP: [&str; 5] = ["\u{f244}", "\u{f243}", "\u{f242}", "\u{f241}", "\u{f240}"];
+const CHARGING_ICON: &str = "\u{f0e7}";
+// 低于这个电量且正在放电时显示警告色
+const LOW_PERCENT: u8 = 15;
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct Battery<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    supply: PowerSupply,
+    ticker: Option<timer::Guard>,
+    // 上一次读到的状态，读取失败时继续显示它
+    status: RefCell<Option<BatteryStatus>>,
+}
+
+impl<'a> Battery<'a> {
+    pub fn new(painter: &'a Painter, supply: PowerSupply) -> Self {
+        Self {
+            x: 1455,
+            y: 0,
+            width: 110,
+            height: 40,
+            painter,
+            supply,
+            ticker: None,
+            status: RefCell::new(None),
+        }
+    }
+
+    /// 定时刷新；插拔电源的即时刷新由 uevent 负责
+    pub fn with_refresh(mut self, scheduler: &Scheduler) -> Self {
+        self.ticker = Some(scheduler.every(REFRESH_INTERVAL, Message::BatteryUpdate));
+        self
+    }
+
+    fn refresh(&self) {
+        match self.supply.read() {
+            Ok(status) => *self.status.borrow_mut() = status,
+            Err(e) => eprintln!("read battery: {e}"),
+        }
+    }
+}
+
+fn icon(status: &BatteryStatus) -> &'static str {
+    if status.state == BatteryState::Charging {
+        return CHARGING_ICON;
+    }
+    let index = status.percent as usize * RAMP.len() / 101;
+    RAMP[index.min(RAMP.len() - 1)]
+}
+
+fn text(status: &BatteryStatus) -> String {
+    match status.time_left {
+        Some(time) => format!("{}% {}", status.percent, battery::format_duration(time)),
+        None => format!("{}%", status.percent),
+    }
+}
+
+impl Component for Battery<'_> {
+    fn name(&self) -> &str {
+        "battery"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        self.refresh();
+        self.painter.clear_area(self.x as f64, self.width as f64)?;
+        let status = self.status.borrow();
+        // 台式机没有电池时不占位置
+        let Some(status) = status.as_ref() else {
+            return Ok(());
+        };
+        let color = match status.state {
+            BatteryState::Discharging if status.percent < LOW_PERCENT => "#ff0000",
+            BatteryState::Charging | BatteryState::Full => "#ff3399",
+            _ => "#ff3329",
+        };
+        let icon = icon(status);
+        let text = text(status);
+        let iw = self.painter.text_width(icon)?;
+        let tw = self.painter.text_width(&text)?;
+
+        self.painter.draw_rounded_background(
+            self.x as f64,
+            (iw + tw + 5.0 + 10.0 * 2.0).min(self.width as f64),
+            10.0,
+            "#475164",
+        )?;
+        self.painter
+            .draw_text(self.x as f64 + 10.0, 10.0, icon, color)?;
+        self.painter
+            .draw_text(self.x as f64 + 10.0 + iw + 5.0, 10.0, &text, color)?;
+        Ok(())
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, _event: &Event) -> Result<(), MyBarError> {
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let status = self.status.borrow();
+        let Some(status) = status.as_ref() else {
+            return Snapshot::default();
+        };
+        Snapshot::new(
+            text(status),
+            json!({
+                "percent": status.percent,
+                "state": format!("{:?}", status.state).to_lowercase(),
+                "time_left": status.time_left.map(|time| time.as_secs()),
+                "ac_online": status.ac_online,
+            }),
+        )
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event
+            && Message::from(ev.data()) == Message::BatteryUpdate
+        {
+            self.draw()?;
+            self.painter.flush()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BatteryState, BatteryStatus, icon};
+
+    #[test]
+    fn icon_ramp() {
+        let status = |percent| BatteryStatus {
+            percent,
+            state: BatteryState::Discharging,
+            time_left: None,
+            ac_online: false,
+        };
+        assert_eq!(icon(&status(0)), "\u{f244}");
+        assert_eq!(icon(&status(50)), "\u{f242}");
+        assert_eq!(icon(&status(100)), "\u{f240}");
+    }
+}
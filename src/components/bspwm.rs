@@ -1,8 +1,9 @@
 use x11::xmu::XmuLookupLatin4;
 
-use super::{Component, Event, Painter};
+use super::{Component, Event, Painter, Snapshot};
 use crate::bspwm::{Bspwm, DesktopEnum};
 use crate::error::MyBarError;
+use serde_json::json;
 use std::any::Any;
 use std::sync::{Arc, Mutex};
 
@@ -29,6 +30,10 @@ impl<'a> BspwmComponent<'a> {
 }
 
 impl<'a> Component for BspwmComponent<'a> {
+    fn name(&self) -> &str {
+        "bspwm"
+    }
+
     fn draw(&self) -> Result<(), MyBarError> {
         if let Ok(bspwm) = self.bspwm.lock() {
             let w = self.width + 2 * 10;
@@ -76,7 +81,7 @@ impl<'a> Component for BspwmComponent<'a> {
 
     fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
         match event {
-            Event::MouseClick { x, y, button } => {
+            Event::MouseClick { x, y, button, .. } => {
                 if let Ok(bspwm) = self.bspwm.lock() {
                     let mut x_offset = 10.0;
                     for monitor in &bspwm.monitors {
@@ -113,4 +118,47 @@ impl<'a> Component for BspwmComponent<'a> {
     fn get_bounds(&self) -> (i16, i16, u16, u16) {
         (self.x, self.y, self.width, self.height)
     }
+
+    /// 文字是各显示器上聚焦的桌面名
+    fn snapshot(&self) -> Snapshot {
+        let Ok(bspwm) = self.bspwm.lock() else {
+            return Snapshot::default();
+        };
+        let mut focused = vec![];
+        let mut monitors = vec![];
+        for monitor in &bspwm.monitors {
+            let mut desktops = vec![];
+            for desktop in &monitor.desktops {
+                if let DesktopEnum::FOCUSED = desktop.state {
+                    focused.push(desktop.name.as_str());
+                }
+                let state = format!("{:?}", desktop.state).to_lowercase();
+                desktops.push(json!({ "name": desktop.name, "state": state }));
+            }
+            monitors.push(json!({
+                "name": monitor.name,
+                "active": monitor.is_active,
+                "desktops": desktops,
+            }));
+        }
+        Snapshot::new(focused.join(" "), json!({ "monitors": monitors }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BspwmComponent;
+    use crate::bspwm::Bspwm;
+    use crate::components::{Component, golden};
+
+    #[test]
+    fn golden_urgent() {
+        // 聚焦的 1 号桌面之外，3 号和 5 号桌面有紧急窗口
+        let bspwm = Bspwm::from_report("WMeDP-1:O1:o2:u3:f4:u5:LT:TT:G");
+        golden::check("bspwm-urgent", |painter| {
+            let component = BspwmComponent::new(painter, bspwm);
+            component.draw()?;
+            Ok(component.get_bounds())
+        });
+    }
 }
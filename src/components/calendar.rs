@@ -0,0 +1,249 @@
+use std::cell::Cell;
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use xcb::x;
+
+use super::Painter;
+use crate::error::{MyBarError, MyResult};
+use crate::x11::{XK_ESCAPE, create_popup, keycodes, popup_geometry};
+
+const CELL_WIDTH: f64 = 36.0;
+const CELL_HEIGHT: f64 = 26.0;
+const PADDING: f64 = 10.0;
+// 标题行 + 星期行 + 最多 6 周
+const ROWS: f64 = 8.0;
+
+/// 点击日期组件后弹出的月历
+///
+/// 弹出时抓取鼠标和键盘，所以窗口外的点击也会发到这个窗口上，借此判断是否点在了外面。
+pub struct Calendar<'a> {
+    conn: &'a xcb::Connection,
+    window: x::Window,
+    width: u16,
+    height: u16,
+    painter: Painter<'a>,
+    // 当前显示月份的 1 号
+    month: Cell<NaiveDate>,
+    week_numbers: bool,
+    // 当前键盘布局下产生 Escape 的 keycode
+    escape: Vec<x::Keycode>,
+}
+
+impl<'a> Calendar<'a> {
+    /// 在 anchor_x 处弹出
+    pub fn open(
+        conn: &'a xcb::Connection,
+        bar: x::Window,
+        visual: x::Visualtype,
+        anchor_x: i16,
+        week_numbers: bool,
+    ) -> MyResult<Self> {
+        let columns = if week_numbers { 8.0 } else { 7.0 };
+        let width = (CELL_WIDTH * columns + PADDING * 2.0) as u16;
+        let height = (CELL_HEIGHT * ROWS + PADDING * 2.0) as u16;
+
+        let (root, geometry) = popup_geometry(conn, bar, anchor_x, width, height)?;
+        let window = create_popup(conn, bar, root, &visual, geometry)?;
+        conn.send_request(&x::MapWindow { window });
+        conn.flush()?;
+
+        let painter = Painter::new(conn, window, visual, width as i32, height as i32)?;
+        let today = chrono::Local::now().date_naive();
+        let calendar = Self {
+            conn,
+            window,
+            width,
+            height,
+            painter,
+            month: Cell::new(today.with_day(1).unwrap()),
+            week_numbers,
+            escape: keycodes(conn, XK_ESCAPE)?,
+        };
+        // 抓取失败时返回错误，calendar 被释放，弹窗随之关闭
+        calendar.grab()?;
+        Ok(calendar)
+    }
+
+    pub fn window(&self) -> x::Window {
+        self.window
+    }
+
+    fn grab(&self) -> MyResult<()> {
+        let pointer = self
+            .conn
+            .wait_for_reply(self.conn.send_request(&x::GrabPointer {
+                owner_events: false,
+                grab_window: self.window,
+                event_mask: x::EventMask::BUTTON_PRESS,
+                pointer_mode: x::GrabMode::Async,
+                keyboard_mode: x::GrabMode::Async,
+                confine_to: x::WINDOW_NONE,
+                cursor: x::CURSOR_NONE,
+                time: x::CURRENT_TIME,
+            }))?;
+        let keyboard = self
+            .conn
+            .wait_for_reply(self.conn.send_request(&x::GrabKeyboard {
+                owner_events: false,
+                grab_window: self.window,
+                time: x::CURRENT_TIME,
+                pointer_mode: x::GrabMode::Async,
+                keyboard_mode: x::GrabMode::Async,
+            }))?;
+        if pointer.status() != x::GrabStatus::Success || keyboard.status() != x::GrabStatus::Success
+        {
+            return Err(MyBarError::Other(
+                "calendar: failed to grab pointer or keyboard".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 滚轮切换月份
+    pub fn scroll(&self, forward: bool) {
+        let month = self.month.get();
+        let month = if forward {
+            month.checked_add_months(Months::new(1))
+        } else {
+            month.checked_sub_months(Months::new(1))
+        };
+        if let Some(month) = month {
+            self.month.set(month);
+        }
+    }
+
+    /// 处理弹窗上的点击，返回 true 表示需要关闭
+    pub fn handle_click(&self, x: i16, y: i16, button: u8) -> bool {
+        match button {
+            4 => self.scroll(false),
+            5 => self.scroll(true),
+            _ => {
+                return x < 0 || y < 0 || x >= self.width as i16 || y >= self.height as i16;
+            }
+        }
+        false
+    }
+
+    /// 处理按键，返回 true 表示需要关闭
+    pub fn handle_key(&self, keycode: u8) -> bool {
+        self.escape.contains(&keycode)
+    }
+
+    pub fn draw(&self) -> Result<(), MyBarError> {
+        let painter = &self.painter;
+        let today = chrono::Local::now().date_naive();
+        let month = self.month.get();
+        let week_offset = if self.week_numbers { CELL_WIDTH } else { 0.0 };
+        let cell_x = |col: f64| PADDING + week_offset + col * CELL_WIDTH;
+        let cell_y = |row: f64| PADDING + row * CELL_HEIGHT;
+
+        painter.clear_area(0.0, self.width as f64)?;
+        painter.set_hex_color("#475164")?;
+        painter
+            .cairo_conn
+            .rectangle(0.0, 0.0, self.width as f64, self.height as f64);
+        painter.cairo_conn.fill()?;
+
+        // 标题：年月
+        let title = month.format("%B %Y").to_string();
+        let tw = painter.text_width(&title)?;
+        painter.draw_text_at(
+            (self.width as f64 - tw) / 2.0,
+            cell_y(0.5),
+            &title,
+            "#ff3329",
+        )?;
+
+        for (i, name) in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]
+            .iter()
+            .enumerate()
+        {
+            painter.draw_text_at(cell_x(i as f64) + 6.0, cell_y(1.5), name, "#aaaaaa")?;
+        }
+
+        for (row, week) in month_grid(month).iter().enumerate() {
+            let y = cell_y(row as f64 + 2.5);
+            if self.week_numbers {
+                let number = week[0].iso_week().week().to_string();
+                painter.draw_text_at(PADDING + 6.0, y, &number, "#666666")?;
+            }
+            for (col, &day) in week.iter().enumerate() {
+                let x = cell_x(col as f64);
+                if day == today {
+                    painter.set_hex_color("#ff3399")?;
+                    painter.cairo_conn.rectangle(
+                        x + 2.0,
+                        y - CELL_HEIGHT / 2.0 + 2.0,
+                        CELL_WIDTH - 4.0,
+                        CELL_HEIGHT - 4.0,
+                    );
+                    painter.cairo_conn.fill()?;
+                }
+                let color = if day.month() == month.month() {
+                    "#ffffff"
+                } else {
+                    "#666666"
+                };
+                painter.draw_text_at(x + 6.0, y, &format!("{:>2}", day.day()), color)?;
+            }
+        }
+        painter.flush()?;
+        Ok(())
+    }
+}
+
+/// 月历上的 6 周，从包含 month（某月 1 号）那一周的周一开始
+fn month_grid(month: NaiveDate) -> [[NaiveDate; 7]; 6] {
+    let offset = month.weekday().num_days_from_monday() as u64;
+    let start = month - Days::new(offset);
+    std::array::from_fn(|row| std::array::from_fn(|col| start + Days::new((row * 7 + col) as u64)))
+}
+
+impl Drop for Calendar<'_> {
+    fn drop(&mut self) {
+        // 先结束 cairo surface，避免之后再往已销毁的窗口上画
+        self.painter.cairo_conn.target().finish();
+        self.conn.send_request(&x::UngrabPointer {
+            time: x::CURRENT_TIME,
+        });
+        self.conn.send_request(&x::UngrabKeyboard {
+            time: x::CURRENT_TIME,
+        });
+        self.conn.send_request(&x::DestroyWindow {
+            window: self.window,
+        });
+        if let Err(e) = self.conn.flush() {
+            eprintln!("close calendar: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Datelike, NaiveDate, Weekday};
+
+    use super::month_grid;
+
+    #[test]
+    fn grid_starts_on_monday() {
+        // 2025-03-01 是周六，前面补上 2 月的最后 5 天
+        let grid = month_grid(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert_eq!(grid[0][0], NaiveDate::from_ymd_opt(2025, 2, 24).unwrap());
+        assert_eq!(grid[0][5], NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert_eq!(grid[5][6], NaiveDate::from_ymd_opt(2025, 4, 6).unwrap());
+        assert!(grid.iter().all(|week| week[0].weekday() == Weekday::Mon));
+
+        // 1 号就是周一时第一行从 1 号开始
+        let grid = month_grid(NaiveDate::from_ymd_opt(2025, 9, 1).unwrap());
+        assert_eq!(grid[0][0], NaiveDate::from_ymd_opt(2025, 9, 1).unwrap());
+    }
+
+    #[test]
+    fn grid_across_year_end() {
+        let grid = month_grid(NaiveDate::from_ymd_opt(2024, 12, 1).unwrap());
+        assert_eq!(grid[0][0], NaiveDate::from_ymd_opt(2024, 11, 25).unwrap());
+        assert_eq!(grid[5][6], NaiveDate::from_ymd_opt(2025, 1, 5).unwrap());
+        // 最后一行是 2025 年的第 1 周
+        assert_eq!(grid[5][0].iso_week().week(), 1);
+    }
+}
@@ -0,0 +1,248 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::{Component, Event, Painter, Snapshot};
+use crate::cpu::{CpuStat, CpuUsage};
+use crate::error::MyBarError;
+use crate::message::Message;
+use crate::scheduler::Scheduler;
+use serde_json::json;
+use xcb::x;
+
+const ICON: &str = "\u{f2db}";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+// 保留最近多少次采样的总使用率
+const HISTORY_LEN: usize = 20;
+// 每个核心的柱子宽度和间距，柱子最高 BAR_HEIGHT
+const BAR_WIDTH: f64 = 4.0;
+const BAR_GAP: f64 = 1.0;
+const BAR_HEIGHT: f64 = 20.0;
+// 历史曲线每个采样点占的宽度
+const HISTORY_STEP: f64 = 3.0;
+
+pub struct Cpu<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    stat: RefCell<CpuStat>,
+    usage: RefCell<CpuUsage>,
+    history: RefCell<VecDeque<f64>>,
+    per_core: bool,
+    show_history: bool,
+    ticker: Option<timer::Guard>,
+    // 上一次绘制的宽度，内容变短时需要擦掉
+    drawn_width: Cell<f64>,
+}
+
+impl<'a> Cpu<'a> {
+    pub fn new(painter: &'a Painter, stat: CpuStat) -> Self {
+        Self {
+            x: 1150,
+            y: 0,
+            width: 120,
+            height: 40,
+            painter,
+            stat: RefCell::new(stat),
+            usage: RefCell::new(CpuUsage::default()),
+            history: RefCell::new(VecDeque::with_capacity(HISTORY_LEN)),
+            per_core: false,
+            show_history: false,
+            ticker: None,
+            drawn_width: Cell::new(0.0),
+        }
+    }
+
+    /// 在百分比后面为每个核心画一根小柱子
+    pub fn with_per_core(mut self, per_core: bool) -> Self {
+        self.per_core = per_core;
+        self
+    }
+
+    /// 在百分比后面画最近一段时间的使用率曲线
+    pub fn with_history(mut self, show_history: bool) -> Self {
+        self.show_history = show_history;
+        self
+    }
+
+    pub fn with_refresh(mut self, scheduler: &Scheduler) -> Self {
+        self.ticker = Some(scheduler.every(REFRESH_INTERVAL, Message::CpuUpdate));
+        self
+    }
+
+    /// 直接显示这个使用率而不等第一次采样，用于截图
+    pub fn with_usage(self, usage: CpuUsage) -> Self {
+        self.history.borrow_mut().push_back(usage.total);
+        *self.usage.borrow_mut() = usage;
+        self
+    }
+
+    // 只在定时器触发时采样，重绘（例如 Expose）不会缩短采样间隔
+    fn sample(&self) {
+        match self.stat.borrow_mut().sample() {
+            Ok(Some(usage)) => {
+                let mut history = self.history.borrow_mut();
+                if history.len() == HISTORY_LEN {
+                    history.pop_front();
+                }
+                history.push_back(usage.total);
+                *self.usage.borrow_mut() = usage;
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("read cpu usage: {e}"),
+        }
+    }
+
+    // 每个核心一根从底部向上的柱子，柱子宽度和间距都乘以 scale
+    fn draw_cores(&self, x: f64, scale: f64, color: &str) -> Result<f64, MyBarError> {
+        let usage = self.usage.borrow();
+        let bottom = (self.height as f64 + BAR_HEIGHT) / 2.0;
+        let (bar, pitch) = (BAR_WIDTH * scale, (BAR_WIDTH + BAR_GAP) * scale);
+        self.painter.set_hex_color("#666666")?;
+        for i in 0..usage.cores.len() {
+            let bx = x + i as f64 * pitch;
+            self.painter
+                .cairo_conn
+                .rectangle(bx, bottom - BAR_HEIGHT, bar, BAR_HEIGHT);
+        }
+        self.painter.cairo_conn.fill()?;
+        self.painter.set_hex_color(color)?;
+        for (i, core) in usage.cores.iter().enumerate() {
+            let bx = x + i as f64 * pitch;
+            let h = BAR_HEIGHT * core.clamp(0.0, 1.0);
+            self.painter.cairo_conn.rectangle(bx, bottom - h, bar, h);
+        }
+        self.painter.cairo_conn.fill()?;
+        Ok(usage.cores.len() as f64 * pitch)
+    }
+
+    // 总使用率的折线，最新的点在最右边
+    fn draw_history(&self, x: f64, scale: f64, color: &str) -> Result<(), MyBarError> {
+        let history = self.history.borrow();
+        let bottom = (self.height as f64 + BAR_HEIGHT) / 2.0;
+        let cr = &self.painter.cairo_conn;
+        self.painter.set_hex_color(color)?;
+        for (i, usage) in history.iter().enumerate() {
+            let px = x + i as f64 * HISTORY_STEP * scale;
+            let py = bottom - BAR_HEIGHT * usage.clamp(0.0, 1.0);
+            if i == 0 {
+                cr.move_to(px, py);
+            } else {
+                cr.line_to(px, py);
+            }
+        }
+        cr.stroke()?;
+        Ok(())
+    }
+}
+
+/// 图形原本的宽度是 graphs，只剩 available 时需要缩小的倍数
+fn graph_scale(graphs: f64, available: f64) -> f64 {
+    if graphs <= available {
+        1.0
+    } else {
+        (available / graphs).max(0.0)
+    }
+}
+
+impl Component for Cpu<'_> {
+    fn name(&self) -> &str {
+        "cpu"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let color = "#ff3329";
+        let text = format!("{:.0}%", self.usage.borrow().total * 100.0);
+        let iw = self.painter.text_width(ICON)?;
+        let tw = self.painter.text_width(&text)?;
+        let cores = self.usage.borrow().cores.len() as f64;
+        let per_core = self.per_core && cores > 0.0;
+        let (mut graphs, mut gaps) = (0.0, 0.0);
+        if per_core {
+            graphs += cores * (BAR_WIDTH + BAR_GAP);
+            gaps += 5.0;
+        }
+        if self.show_history {
+            graphs += HISTORY_LEN as f64 * HISTORY_STEP;
+            gaps += 5.0;
+        }
+        // 核心多时柱子和曲线一起缩窄，保证不超出组件的范围
+        let fixed = iw + 5.0 + tw + gaps + 10.0 * 2.0;
+        let scale = graph_scale(graphs, self.width as f64 - fixed);
+        let w = fixed + graphs * scale;
+
+        if self.drawn_width.get() > w {
+            self.painter
+                .clear_area(self.x as f64, self.drawn_width.get())?;
+        }
+        self.drawn_width.set(w);
+        self.painter
+            .draw_rounded_background(self.x as f64, w, 10.0, "#475164")?;
+        let mut x = self.x as f64 + 10.0;
+        self.painter.draw_text(x, 10.0, ICON, color)?;
+        x += iw + 5.0;
+        self.painter.draw_text(x, 10.0, &text, color)?;
+        x += tw + 5.0;
+        if per_core {
+            x += self.draw_cores(x, scale, color)? + 5.0;
+        }
+        if self.show_history {
+            self.draw_history(x, scale, color)?;
+        }
+        Ok(())
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, _event: &Event) -> Result<(), MyBarError> {
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let usage = self.usage.borrow();
+        Snapshot::new(
+            format!("{:.0}%", usage.total * 100.0),
+            json!({ "total": usage.total, "cores": usage.cores }),
+        )
+    }
+
+    fn update(&self) -> Result<(), MyBarError> {
+        self.sample();
+        self.draw()
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event
+            && Message::from(ev.data()) == Message::CpuUpdate
+        {
+            self.sample();
+            self.draw()?;
+            self.painter.flush()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::graph_scale;
+
+    #[test]
+    fn shrink_graphs_to_available_width() {
+        assert_eq!(graph_scale(45.0, 60.0), 1.0);
+        assert_eq!(graph_scale(80.0, 40.0), 0.5);
+        assert_eq!(graph_scale(80.0, -5.0), 0.0);
+    }
+}
@@ -1,6 +1,14 @@
-use super::{Component, Event, Painter};
-use crate::error::MyBarError;
+use std::cell::{Cell, RefCell};
+
+use super::calendar::Calendar;
+use super::{Component, Event, Painter, Snapshot};
+use crate::error::{MyBarError, MyResult};
+use crate::util;
 use chrono;
+use chrono::format::{Item, StrftimeItems};
+use chrono_tz::Tz;
+use serde_json::json;
+use xcb::x;
 
 pub struct Date<'a> {
     x: i16,
@@ -8,36 +16,180 @@ pub struct Date<'a> {
     width: u16,
     height: u16,
     painter: &'a Painter<'a>,
+    format: String,
+    // 左键切换的备用格式
+    alt_format: String,
+    show_alt: Cell<bool>,
+    // 额外的时区，滚轮循环切换；下标 0 表示本地时间
+    zones: Vec<Tz>,
+    zone_index: Cell<usize>,
+    // 上一次绘制的宽度，切换成更短的格式时需要擦掉
+    drawn_width: Cell<f64>,
+    // 右键弹出日历所需的连接、状态栏窗口和视觉
+    popup: Option<(&'a xcb::Connection, x::Window, x::Visualtype)>,
+    week_numbers: bool,
+    calendar: RefCell<Option<Calendar<'a>>>,
+    // 固定显示的时刻，为 None 时显示当前时间
+    time: Option<chrono::DateTime<chrono::FixedOffset>>,
 }
 
 impl<'a> Date<'a> {
     pub fn new(painter: &'a Painter<'a>) -> Self {
         Self {
-            x: 820,
+            x: 490,
             y: 0,
-            width: 200,
+            width: 250,
             height: 40,
             painter,
+            format: "%a %b %e %T %Y".to_string(),
+            alt_format: "%Y-%m-%d %H:%M".to_string(),
+            show_alt: Cell::new(false),
+            zones: vec![],
+            zone_index: Cell::new(0),
+            drawn_width: Cell::new(0.0),
+            popup: None,
+            week_numbers: false,
+            calendar: RefCell::new(None),
+            time: None,
         }
     }
 
+    /// 启用右键弹出日历
+    pub fn with_calendar(
+        mut self,
+        conn: &'a xcb::Connection,
+        bar: x::Window,
+        visual: x::Visualtype,
+        week_numbers: bool,
+    ) -> Self {
+        self.popup = Some((conn, bar, visual));
+        self.week_numbers = week_numbers;
+        self
+    }
+
+    /// strftime 格式，无效的格式返回错误（chrono 在输出时遇到无效格式会 panic）
+    pub fn with_format(mut self, format: &str, alt_format: &str) -> MyResult<Self> {
+        check_format(format)?;
+        check_format(alt_format)?;
+        self.format = format.to_string();
+        self.alt_format = alt_format.to_string();
+        Ok(self)
+    }
+
+    /// 时区使用 IANA 名称，例如 "Asia/Tokyo"，无法识别的名称会被忽略
+    pub fn with_zones(mut self, zones: &[&str]) -> Self {
+        self.zones = zones
+            .iter()
+            .filter_map(|name| match name.parse::<Tz>() {
+                Ok(tz) => Some(tz),
+                Err(e) => {
+                    eprintln!("unknown time zone {name}: {e}");
+                    None
+                }
+            })
+            .collect();
+        self
+    }
+
+    /// 总是显示这个时刻（偏移量当作本地时区），用于截图和测试
+    pub fn with_time(mut self, time: chrono::DateTime<chrono::FixedOffset>) -> Self {
+        self.time = Some(time);
+        self
+    }
+
     pub fn flush(&self) -> Result<(), MyBarError> {
         self.painter.flush()?;
         Ok(())
     }
+
+    fn text(&self) -> String {
+        let format = if self.show_alt.get() {
+            &self.alt_format
+        } else {
+            &self.format
+        };
+        let zone = match self.zone_index.get() {
+            0 => None,
+            i => self.zones.get(i - 1).copied(),
+        };
+        match self.time {
+            Some(time) => format_time(time, zone, format),
+            None => format_time(chrono::Local::now(), zone, format),
+        }
+    }
+
+    fn toggle_calendar(&self) -> Result<(), MyBarError> {
+        let Some((conn, bar, visual)) = self.popup else {
+            return Ok(());
+        };
+        let mut calendar = self.calendar.borrow_mut();
+        if calendar.take().is_none() {
+            *calendar = Some(Calendar::open(
+                conn,
+                bar,
+                visual,
+                self.x,
+                self.week_numbers,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn cycle_zone(&self, forward: bool) {
+        let count = self.zones.len() + 1;
+        let i = self.zone_index.get();
+        let i = if forward { i + 1 } else { i + count - 1 };
+        self.zone_index.set(i % count);
+    }
+}
+
+fn check_format(format: &str) -> MyResult<()> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(MyBarError::Other(format!("invalid date format {format:?}")));
+    }
+    Ok(())
+}
+
+/// 按格式输出时间，指定时区时在末尾加上时区名
+fn format_time<T>(now: chrono::DateTime<T>, zone: Option<Tz>, format: &str) -> String
+where
+    T: chrono::TimeZone,
+    T::Offset: std::fmt::Display,
+{
+    match zone {
+        Some(tz) => format!("{} {}", now.with_timezone(&tz).format(format), tz.name()),
+        None => now.format(format).to_string(),
+    }
 }
 
 impl Component for Date<'_> {
+    fn name(&self) -> &str {
+        "date"
+    }
+
+    fn close_popups(&self) {
+        self.calendar.borrow_mut().take();
+    }
+
     fn draw(&self) -> Result<(), MyBarError> {
-        let now = chrono::Local::now();
-        let text = now.format("%a %b %e %T %Y").to_string();
-        let tw = self.painter.text_width(&text)?;
-        let w = tw + 30.0 * 2.0;
+        // 文字较长时两边的留白从 30 缩到 10，加上时区名后还放不下就截掉末尾
+        let max = self.width as f64 - 10.0 * 2.0;
+        let text = util::ellipsize(&self.text(), max, false, |s| {
+            self.painter.text_width(s).unwrap_or(f64::MAX)
+        });
+        let tw = self.painter.text_width(&text)?.min(max);
+        let padding = ((self.width as f64 - tw) / 2.0).clamp(10.0, 30.0);
+        let w = tw + padding * 2.0;
 
+        if self.drawn_width.get() > w {
+            self.painter
+                .clear_area(self.x as f64, self.drawn_width.get())?;
+        }
+        self.drawn_width.set(w);
         self.painter
             .draw_rounded_background(self.x as f64, w, 10.0, "#475164")?;
         self.painter
-            .draw_text(self.x as f64 + 30.0, 10.0, &text, "#ff3329")?;
+            .draw_text(self.x as f64 + padding, 10.0, &text, "#ff3329")?;
 
         Ok(())
     }
@@ -51,15 +203,24 @@ impl Component for Date<'_> {
 
     fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
         match event {
-            Event::MouseClick { x, y, button } => {
-                if self.contains_point(*x, *y) {
-                    println!("Date clicked at ({}, {})", x, y);
-                    // TODO: 实现日期组件点击逻辑
+            Event::MouseClick { x, y, button, .. } if self.contains_point(*x, *y) => {
+                match *button {
+                    // 左键切换格式
+                    1 => self.show_alt.set(!self.show_alt.get()),
+                    // 右键弹出日历
+                    3 => return self.toggle_calendar(),
+                    // 滚轮切换时区
+                    4 => self.cycle_zone(true),
+                    5 => self.cycle_zone(false),
+                    _ => return Ok(()),
                 }
+                self.draw()?;
+                self.flush()?;
             }
-            Event::KeyPress { keycode } => {
+            Event::KeyPress { .. } => {
                 // TODO: 实现日期组件键盘控制逻辑
             }
+            _ => {}
         }
         Ok(())
     }
@@ -67,4 +228,83 @@ impl Component for Date<'_> {
     fn get_bounds(&self) -> (i16, i16, u16, u16) {
         (self.x, self.y, self.width, self.height)
     }
+
+    fn snapshot(&self) -> Snapshot {
+        let zone = match self.zone_index.get() {
+            0 => None,
+            i => self.zones.get(i - 1).map(|tz| tz.name()),
+        };
+        Snapshot::new(
+            self.text(),
+            json!({ "zone": zone, "calendar": self.calendar.borrow().is_some() }),
+        )
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        let mut calendar = self.calendar.borrow_mut();
+        let Some(cal) = calendar.as_ref() else {
+            return Ok(false);
+        };
+        let close = match event {
+            xcb::Event::X(x::Event::Expose(ev)) if ev.window() == cal.window() => {
+                if ev.count() == 0 {
+                    cal.draw()?;
+                }
+                false
+            }
+            xcb::Event::X(x::Event::ButtonPress(ev)) if ev.event() == cal.window() => {
+                let close = cal.handle_click(ev.event_x(), ev.event_y(), ev.detail());
+                if !close {
+                    cal.draw()?;
+                }
+                close
+            }
+            xcb::Event::X(x::Event::KeyPress(ev)) if ev.event() == cal.window() => {
+                cal.handle_key(ev.detail())
+            }
+            _ => return Ok(false),
+        };
+        if close {
+            calendar.take();
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::{Date, check_format, format_time};
+    use crate::components::{Component, golden};
+
+    #[test]
+    fn format_in_zone() {
+        let now = chrono::Utc.with_ymd_and_hms(2025, 3, 1, 12, 30, 0).unwrap();
+        let tz = "Asia/Tokyo".parse().unwrap();
+        assert_eq!(
+            format_time(now, Some(tz), "%Y-%m-%d %H:%M"),
+            "2025-03-01 21:30 Asia/Tokyo"
+        );
+    }
+
+    #[test]
+    fn reject_invalid_format() {
+        assert!(check_format("%Y-%m-%d %H:%M").is_ok());
+        assert!(check_format("%Q").is_err());
+        assert!(check_format("%").is_err());
+    }
+
+    #[test]
+    fn golden_fixed_time() {
+        let time = chrono::FixedOffset::east_opt(8 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2025, 3, 1, 12, 30, 0)
+            .unwrap();
+        golden::check("date", |painter| {
+            let date = Date::new(painter).with_time(time);
+            date.draw()?;
+            Ok(date.get_bounds())
+        });
+    }
 }
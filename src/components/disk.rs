@@ -0,0 +1,228 @@
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::time::Duration;
+
+use super::{Component, Event, Painter, Snapshot};
+use crate::disk::{self, DiskUsage};
+use crate::error::MyBarError;
+use crate::message::Message;
+use crate::scheduler::Scheduler;
+use crate::util;
+use serde_json::json;
+use xcb::x;
+
+const ICON: &str = "\u{f0a0}";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct Disk<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    // 为空时从 /proc/self/mounts 自动发现
+    mounts: RefCell<Vec<String>>,
+    // 当前显示的挂载点，点击切换
+    index: Cell<usize>,
+    show_free: bool,
+    // 不为空时不调用 statvfs，总是显示这个用量
+    fixed: Option<DiskUsage>,
+    warning: f64,
+    critical: f64,
+    ticker: Option<timer::Guard>,
+    drawn_width: Cell<f64>,
+    // 上一次绘制时的挂载点和用量，给 IPC 的 state 命令用
+    shown: RefCell<Option<(String, DiskUsage)>>,
+}
+
+impl<'a> Disk<'a> {
+    pub fn new(painter: &'a Painter) -> Self {
+        Self {
+            x: 1358,
+            y: 0,
+            width: 88,
+            height: 40,
+            painter,
+            mounts: RefCell::new(vec![]),
+            index: Cell::new(0),
+            show_free: true,
+            fixed: None,
+            warning: 0.8,
+            critical: 0.95,
+            ticker: None,
+            drawn_width: Cell::new(0.0),
+            shown: RefCell::new(None),
+        }
+    }
+
+    pub fn with_mounts(self, mounts: &[&str]) -> Self {
+        *self.mounts.borrow_mut() = mounts.iter().map(|m| m.to_string()).collect();
+        self
+    }
+
+    /// true 显示剩余空间，false 显示已用空间
+    pub fn with_free(mut self, show_free: bool) -> Self {
+        self.show_free = show_free;
+        self
+    }
+
+    /// 使用率超过 warning 显示警告色，超过 critical 显示红色
+    pub fn with_thresholds(mut self, warning: f64, critical: f64) -> Self {
+        self.warning = warning;
+        self.critical = critical;
+        self
+    }
+
+    /// 只显示 mount 并且用量固定为 usage，用于截图
+    pub fn with_usage(mut self, mount: &str, usage: DiskUsage) -> Self {
+        *self.mounts.get_mut() = vec![mount.to_string()];
+        self.fixed = Some(usage);
+        self
+    }
+
+    pub fn with_refresh(mut self, scheduler: &Scheduler) -> Self {
+        self.ticker = Some(scheduler.every(REFRESH_INTERVAL, Message::DiskUpdate));
+        self
+    }
+
+    fn current(&self) -> Option<String> {
+        let mut mounts = self.mounts.borrow_mut();
+        if mounts.is_empty() {
+            match disk::discover_mounts(Path::new("/proc")) {
+                Ok(found) => *mounts = found,
+                Err(e) => eprintln!("discover mounts: {e}"),
+            }
+        }
+        mounts.get(self.index.get() % mounts.len().max(1)).cloned()
+    }
+
+    fn cycle(&self, forward: bool) {
+        let count = self.mounts.borrow().len().max(1);
+        let i = self.index.get();
+        let i = if forward { i + 1 } else { i + count - 1 };
+        self.index.set(i % count);
+    }
+
+    fn text(&self, mount: &str, usage: &DiskUsage) -> String {
+        let size = if self.show_free {
+            usage.available
+        } else {
+            usage.used()
+        };
+        format!("{mount} {}", disk::format_size(size))
+    }
+
+    fn color(&self, usage: &DiskUsage) -> &'static str {
+        let ratio = usage.used_ratio();
+        if ratio >= self.critical {
+            "#ff0000"
+        } else if ratio >= self.warning {
+            "#ff9900"
+        } else {
+            "#ff3329"
+        }
+    }
+}
+
+impl Component for Disk<'_> {
+    fn name(&self) -> &str {
+        "disk"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let Some(mount) = self.current() else {
+            return Ok(());
+        };
+        let usage = match self.fixed {
+            Some(usage) => Ok(usage),
+            None => disk::statvfs(&mount),
+        };
+        let (text, color) = match usage {
+            Ok(usage) => {
+                let shown = (self.text(&mount, &usage), self.color(&usage));
+                *self.shown.borrow_mut() = Some((mount, usage));
+                shown
+            }
+            Err(e) => {
+                eprintln!("statvfs {mount}: {e}");
+                *self.shown.borrow_mut() = None;
+                (format!("{mount} ?"), "#666666")
+            }
+        };
+        let iw = self.painter.text_width(ICON)?;
+        // 挂载点的路径很长时截掉中间
+        let max = self.width as f64 - iw - 5.0 - 10.0 * 2.0;
+        let text = util::ellipsize(&text, max, true, |s| {
+            self.painter.text_width(s).unwrap_or(f64::MAX)
+        });
+        let tw = self.painter.text_width(&text)?;
+        let w = iw + 5.0 + tw + 10.0 * 2.0;
+
+        if self.drawn_width.get() > w {
+            self.painter
+                .clear_area(self.x as f64, self.drawn_width.get())?;
+        }
+        self.drawn_width.set(w);
+        self.painter
+            .draw_rounded_background(self.x as f64, w, 10.0, "#475164")?;
+        self.painter
+            .draw_text(self.x as f64 + 10.0, 10.0, ICON, color)?;
+        self.painter
+            .draw_text(self.x as f64 + 10.0 + iw + 5.0, 10.0, &text, color)?;
+        Ok(())
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
+        if let Event::MouseClick { x, y, button, .. } = event
+            && self.contains_point(*x, *y)
+        {
+            match *button {
+                // 左键下一个挂载点，右键上一个
+                1 => self.cycle(true),
+                3 => self.cycle(false),
+                _ => return Ok(()),
+            }
+            self.draw()?;
+            self.painter.flush()?;
+        }
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let shown = self.shown.borrow();
+        let Some((mount, usage)) = shown.as_ref() else {
+            return Snapshot::default();
+        };
+        Snapshot::new(
+            self.text(mount, usage),
+            json!({
+                "mount": mount,
+                "total": usage.total,
+                "available": usage.available,
+                "used": usage.used(),
+            }),
+        )
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event
+            && Message::from(ev.data()) == Message::DiskUpdate
+        {
+            self.draw()?;
+            self.painter.flush()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
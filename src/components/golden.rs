@@ -0,0 +1,212 @@
+// 组件截图测试：把组件画到图片上，和 fixtures/golden 里的参考图比较
+//
+// 参考图缺失或需要更新时，用 MYBAR_UPDATE_GOLDEN=1 cargo test 重新生成。
+// 比较失败时把实际结果和差异图写到 <target 目录>/golden，差异图里超出容差的像素标成红色。
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use super::Painter;
+use crate::error::{MyBarError, MyResult};
+
+const WIDTH: i32 = 1920;
+const HEIGHT: i32 = 40;
+const REFERENCE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/golden");
+// 直接用 FreeType 打开仓库里的字体文件，不经过 fontconfig，参考图不受本机装了哪些字体影响。
+// 它是 DejaVu Sans Mono 加上图标码位的测试字形，由 fixtures/fonts/build.py 生成
+const FONT: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/fixtures/fonts/MybarTestMono.ttf"
+);
+// 不同版本的 cairo/freetype 抗锯齿会有细微差别：
+// 单个通道相差不超过 CHANNEL_TOLERANCE 算相同，不同的像素不超过 MAX_DIFF_RATIO 算通过
+const CHANNEL_TOLERANCE: u8 = 24;
+const MAX_DIFF_RATIO: f64 = 0.005;
+
+/// 在一张 1920x40 的透明图片上调用 draw 画出组件，draw 返回组件的 get_bounds()，
+/// 从组件的 x 截到最右边画过的像素，再和参考图 `<name>.png` 比较
+pub fn check(name: &str, draw: impl FnOnce(&Painter) -> MyResult<(i16, i16, u16, u16)>) {
+    let actual = render(draw).unwrap_or_else(|e| panic!("{name}: render: {e}"));
+    let reference_path = Path::new(REFERENCE_DIR).join(format!("{name}.png"));
+    if std::env::var_os("MYBAR_UPDATE_GOLDEN").is_some() {
+        write_png(&actual, &reference_path);
+        return;
+    }
+    let mut reference = match File::open(&reference_path) {
+        Ok(mut file) => cairo::ImageSurface::create_from_png(&mut file)
+            .unwrap_or_else(|e| panic!("{}: {e}", reference_path.display())),
+        Err(e) => {
+            let actual_path = write_output(&actual, name, "actual");
+            panic!(
+                "{}: {e}; actual image written to {}, run with MYBAR_UPDATE_GOLDEN=1 to accept it",
+                reference_path.display(),
+                actual_path.display()
+            );
+        }
+    };
+    let mut actual = actual;
+    let size = (actual.width(), actual.height());
+    let reference_size = (reference.width(), reference.height());
+    if size != reference_size {
+        // 补成一样大再比较，多出来的部分在差异图里是红色
+        let width = size.0.max(reference_size.0);
+        let height = size.1.max(reference_size.1);
+        let diff = diff(
+            &mut pad(&actual, width, height),
+            &mut pad(&reference, width, height),
+        );
+        let actual_path = write_output(&actual, name, "actual");
+        let diff_path = write_output(&diff.image, name, "diff");
+        panic!(
+            "{name}: size {size:?} differs from reference {reference_size:?}, see {} and {}",
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+    let diff = diff(&mut actual, &mut reference);
+    let ratio = diff.count as f64 / (size.0 * size.1) as f64;
+    if ratio > MAX_DIFF_RATIO {
+        let actual_path = write_output(&actual, name, "actual");
+        let diff_path = write_output(&diff.image, name, "diff");
+        panic!(
+            "{name}: {} of {} pixels differ from reference, see {} and {}",
+            diff.count,
+            size.0 * size.1,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn render(
+    draw: impl FnOnce(&Painter) -> MyResult<(i16, i16, u16, u16)>,
+) -> MyResult<cairo::ImageSurface> {
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, WIDTH, HEIGHT)?;
+    // painter 持有 surface 的引用，读像素之前要先释放
+    let x = {
+        let painter = Painter::for_surface(&surface, WIDTH, HEIGHT)?;
+        painter.cairo_conn.set_font_face(&font_face()?);
+        let (x, ..) = draw(&painter)?;
+        painter.flush()?;
+        x.clamp(0, WIDTH as i16 - 1) as i32
+    };
+    crop(surface, x)
+}
+
+fn font_face() -> MyResult<cairo::FontFace> {
+    let error = |e: cairo::freetype::Error| MyBarError::Other(format!("{FONT}: {e}"));
+    let library = cairo::freetype::Library::init().map_err(error)?;
+    let face = library.new_face(FONT, 0).map_err(error)?;
+    Ok(cairo::FontFace::create_from_ft(&face)?)
+}
+
+/// 截取 [x, 最右边不透明的像素] 这一段，组件画的位置变了也能发现
+fn crop(mut surface: cairo::ImageSurface, x: i32) -> MyResult<cairo::ImageSurface> {
+    let stride = surface.stride() as usize;
+    let data = surface.data().map_err(borrow_error)?;
+    let right = (x..WIDTH)
+        .rev()
+        .find(|&column| {
+            (0..HEIGHT as usize).any(|row| pixel(&data, stride, row, column as usize)[3] != 0)
+        })
+        .unwrap_or(x);
+    let width = right - x + 1;
+    let mut cropped = cairo::ImageSurface::create(cairo::Format::ARgb32, width, HEIGHT)?;
+    let cropped_stride = cropped.stride() as usize;
+    {
+        let mut cropped_data = cropped.data().map_err(borrow_error)?;
+        let len = width as usize * 4;
+        for row in 0..HEIGHT as usize {
+            let from = row * stride + x as usize * 4;
+            let to = row * cropped_stride;
+            cropped_data[to..to + len].copy_from_slice(&data[from..from + len]);
+        }
+    }
+    Ok(cropped)
+}
+
+/// 复制到一张 width x height 的透明图片的左上角
+fn pad(surface: &cairo::ImageSurface, width: i32, height: i32) -> cairo::ImageSurface {
+    let padded = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)
+        .expect("create padded image");
+    let cr = cairo::Context::new(&padded).expect("padded image context");
+    cr.set_source_surface(surface, 0.0, 0.0)
+        .expect("padded image source");
+    cr.paint().expect("pad image");
+    drop(cr);
+    padded
+}
+
+fn borrow_error(e: cairo::BorrowError) -> MyBarError {
+    MyBarError::Other(format!("image data: {e}"))
+}
+
+struct Diff {
+    image: cairo::ImageSurface,
+    count: usize,
+}
+
+/// 超出容差的像素画成红色，其余像素画成变暗的参考图
+fn diff(actual: &mut cairo::ImageSurface, reference: &mut cairo::ImageSurface) -> Diff {
+    let (width, height) = (actual.width(), actual.height());
+    let stride = actual.stride() as usize;
+    let reference_stride = reference.stride() as usize;
+    let mut image = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)
+        .expect("create diff image");
+    let mut count = 0;
+    {
+        let actual = actual.data().expect("actual image data");
+        let reference = reference.data().expect("reference image data");
+        let mut out = image.data().expect("diff image data");
+        for row in 0..height as usize {
+            for column in 0..width as usize {
+                let a = pixel(&actual, stride, row, column);
+                let r = pixel(&reference, reference_stride, row, column);
+                let differs = a
+                    .iter()
+                    .zip(r)
+                    .any(|(a, r)| a.abs_diff(*r) > CHANNEL_TOLERANCE);
+                // ARGB32 按本机字节序存放，小端机器上是 BGRA
+                let value: u32 = if differs {
+                    count += 1;
+                    0xffff0000
+                } else {
+                    let gray = (r[0] as u32 + r[1] as u32 + r[2] as u32) / 12;
+                    0xff000000 | gray << 16 | gray << 8 | gray
+                };
+                let offset = row * stride + column * 4;
+                out[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+            }
+        }
+    }
+    Diff { image, count }
+}
+
+fn pixel(data: &[u8], stride: usize, row: usize, column: usize) -> &[u8] {
+    let offset = row * stride + column * 4;
+    &data[offset..offset + 4]
+}
+
+// 测试程序在 <target>/<profile>/deps 下，这样 CARGO_TARGET_DIR 和 build.target-dir 都能生效
+fn output_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.ancestors().nth(3).map(Path::to_path_buf))
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("target"))
+        .join("golden")
+}
+
+fn write_output(surface: &cairo::ImageSurface, name: &str, kind: &str) -> PathBuf {
+    let path = output_dir().join(format!("{name}.{kind}.png"));
+    write_png(surface, &path);
+    path
+}
+
+fn write_png(surface: &cairo::ImageSurface, path: &Path) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap_or_else(|e| panic!("{}: {e}", dir.display()));
+    }
+    let mut file = File::create(path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    surface
+        .write_to_png(&mut file)
+        .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+}
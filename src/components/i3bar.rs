@@ -0,0 +1,378 @@
+use std::cell::{Cell, RefCell};
+use std::io::{BufRead, BufReader};
+use std::process::{ChildStdin, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::tail::{Running, next_backoff};
+use super::{Component, Event, Painter, Snapshot};
+use crate::command;
+use crate::error::MyBarError;
+use crate::i3bar::{self, Align, Block, ClickEvent, ClickWriter, Header, MinWidth};
+use crate::message::Message;
+use serde_json::json;
+use xcb::x;
+
+#[derive(Default)]
+struct Shared {
+    blocks: Vec<Block>,
+    dirty: bool,
+}
+
+// 一个块绘制的位置，点击时用来找到对应的块
+struct Placed {
+    x: f64,
+    width: f64,
+    name: Option<String>,
+    instance: Option<String>,
+}
+
+/// 运行 i3status、i3blocks 这类 i3bar 协议的生产者，把它的整条状态行画成一个组件
+///
+/// 生产者在头部要求 click_events 时，点击会以 JSON 写回它的 stdin。
+/// 和 Tail 一样，进程退出后按退避时间重启，组件被 drop 时结束进程。
+pub struct I3bar<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    command: String,
+    name: String,
+    shared: Arc<Mutex<Shared>>,
+    running: Arc<Mutex<Running>>,
+    // 生产者要求点击事件时才有
+    clicks: Arc<Mutex<Option<ClickWriter<ChildStdin>>>>,
+    stop: Option<Sender<()>>,
+    layout: RefCell<Vec<Placed>>,
+    drawn_width: Cell<f64>,
+}
+
+impl<'a> I3bar<'a> {
+    pub fn new(painter: &'a Painter, command: &str) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 40,
+            painter,
+            command: command.to_string(),
+            name: command::program_name(command),
+            shared: Arc::new(Mutex::new(Shared::default())),
+            running: Arc::new(Mutex::new(Running::default())),
+            clicks: Arc::new(Mutex::new(None)),
+            stop: None,
+            layout: RefCell::new(vec![]),
+            drawn_width: Cell::new(0.0),
+        }
+    }
+
+    /// IPC 命令里用的名字，默认是命令的程序名
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_position(mut self, x: i16, width: u16) -> Self {
+        self.x = x;
+        self.width = width;
+        self
+    }
+
+    pub fn start(mut self, conn: &Arc<xcb::Connection>, window: x::Window) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let conn = Arc::clone(conn);
+        let shared = Arc::clone(&self.shared);
+        let running = Arc::clone(&self.running);
+        let clicks = Arc::clone(&self.clicks);
+        let command = self.command.clone();
+        std::thread::spawn(move || {
+            let mut backoff = None;
+            loop {
+                let started = Instant::now();
+                let producer = Producer {
+                    running: &running,
+                    clicks: &clicks,
+                    shared: &shared,
+                };
+                if let Err(e) = producer.run(&command, &conn, window) {
+                    eprintln!("i3bar {command}: {e}");
+                }
+                if let Ok(mut clicks) = clicks.lock() {
+                    clicks.take();
+                }
+                if stopped.try_recv() == Err(TryRecvError::Disconnected) {
+                    break;
+                }
+                let wait = next_backoff(backoff, started.elapsed());
+                backoff = Some(wait);
+                eprintln!("i3bar {command}: exited, restarting in {wait:?}");
+                match stopped.recv_timeout(wait) {
+                    Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+        self.stop = Some(stop);
+        self
+    }
+
+    fn min_width(&self, block: &Block) -> Result<f64, MyBarError> {
+        Ok(match &block.min_width {
+            Some(MinWidth::Pixels(width)) => *width,
+            Some(MinWidth::Text(text)) => self.painter.text_width(text)?,
+            None => 0.0,
+        })
+    }
+
+    // x、y 是相对状态栏的坐标，用来找到点击的块；协议里的 x、y 是屏幕坐标
+    fn click(&self, x: i16, y: i16, root: (i16, i16), button: u8) {
+        let layout = self.layout.borrow();
+        let Some(placed) = layout
+            .iter()
+            .find(|p| (x as f64) >= p.x && (x as f64) < p.x + p.width)
+        else {
+            return;
+        };
+        let event = ClickEvent {
+            name: placed.name.clone(),
+            instance: placed.instance.clone(),
+            button,
+            x: root.0 as i32,
+            y: root.1 as i32,
+            relative_x: (x as f64 - placed.x) as i32,
+            relative_y: (y - self.y) as i32,
+            width: placed.width as i32,
+            height: self.height as i32,
+        };
+        if let Ok(mut clicks) = self.clicks.lock()
+            && let Some(writer) = clicks.as_mut()
+            && let Err(e) = writer.send(&event)
+        {
+            eprintln!("i3bar {}: send click: {e}", self.command);
+        }
+    }
+}
+
+// 后台线程运行一次生产者需要的共享状态
+struct Producer<'s> {
+    running: &'s Mutex<Running>,
+    clicks: &'s Mutex<Option<ClickWriter<ChildStdin>>>,
+    shared: &'s Mutex<Shared>,
+}
+
+impl Producer<'_> {
+    // 运行一次生产者，直到它的 stdout 关闭
+    fn run(
+        &self,
+        command: &str,
+        conn: &xcb::Connection,
+        window: x::Window,
+    ) -> Result<(), MyBarError> {
+        let mut process = command::spawn_streaming(command, Stdio::piped())?;
+        let (Some(stdout), Some(stdin)) = (process.stdout.take(), process.stdin.take()) else {
+            command::terminate(&mut process);
+            return Err(MyBarError::Other("no stdio pipes".to_string()));
+        };
+        if !Running::adopt(self.running, process) {
+            return Ok(());
+        }
+        let result = self.read(BufReader::new(stdout), stdin, conn, window);
+        // 进程自己退出了（或者输出无法解析），回收它
+        Running::reap(self.running);
+        result
+    }
+
+    fn read(
+        &self,
+        stdout: impl BufRead,
+        stdin: ChildStdin,
+        conn: &xcb::Connection,
+        window: x::Window,
+    ) -> Result<(), MyBarError> {
+        let mut lines = stdout.lines();
+        let Some(header) = lines.next() else {
+            return Ok(());
+        };
+        let header = Header::parse(&header?)?;
+        if header.click_events
+            && let Ok(mut clicks) = self.clicks.lock()
+        {
+            *clicks = Some(ClickWriter::new(stdin)?);
+        }
+        for line in lines {
+            let blocks = match i3bar::parse_line(&line?) {
+                Ok(Some(blocks)) => blocks,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("i3bar: invalid status line: {e}");
+                    continue;
+                }
+            };
+            if let Ok(mut shared) = self.shared.lock() {
+                shared.blocks = blocks;
+                shared.dirty = true;
+            }
+            Message::I3barUpdate.send(conn, window)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for I3bar<'_> {
+    fn drop(&mut self) {
+        self.stop.take();
+        Running::stop(&self.running);
+    }
+}
+
+impl Component for I3bar<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let blocks = match self.shared.lock() {
+            Ok(mut shared) => {
+                shared.dirty = false;
+                shared.blocks.clone()
+            }
+            Err(_) => return Ok(()),
+        };
+        let left = self.x as f64;
+        let right = left + self.width as f64 - 10.0;
+
+        // 先排版：块的宽度至少是 min_width，块之间留 separator_block_width
+        let mut placed = vec![];
+        let mut separators = vec![];
+        let mut x = left + 10.0;
+        for (i, block) in blocks.iter().enumerate() {
+            let text = block.text();
+            if text.is_empty() {
+                continue;
+            }
+            let tw = self.painter.text_width(&text)?;
+            let bw = tw.max(self.min_width(block)?);
+            if x + bw > right {
+                break;
+            }
+            placed.push((x, bw, tw, text, block));
+            x += bw;
+            if i + 1 < blocks.len() {
+                if block.separator {
+                    separators.push(x + block.separator_block_width / 2.0);
+                }
+                x += block.separator_block_width;
+            }
+        }
+        let Some(&(last_x, last_w, ..)) = placed.last() else {
+            self.painter.clear_area(left, self.drawn_width.get())?;
+            self.drawn_width.set(0.0);
+            self.layout.borrow_mut().clear();
+            return Ok(());
+        };
+        let w = last_x + last_w + 10.0 - left;
+
+        if self.drawn_width.get() > w {
+            self.painter.clear_area(left, self.drawn_width.get())?;
+        }
+        self.drawn_width.set(w);
+        self.painter
+            .draw_rounded_background(left, w, 10.0, "#475164")?;
+        for (bx, bw, tw, text, block) in &placed {
+            // 无效的颜色只影响这一个块，按没有设置处理
+            if let Some(background) = block.background.as_deref().and_then(i3bar::to_argb) {
+                self.painter.draw_rectangle(*bx, *bw, &background)?;
+            }
+            let tx = match block.align {
+                Align::Left => *bx,
+                Align::Center => bx + (bw - tw) / 2.0,
+                Align::Right => bx + bw - tw,
+            };
+            let color = match block.color.as_deref().and_then(i3bar::to_argb) {
+                _ if block.urgent => "#ff0000".to_string(),
+                Some(color) => color,
+                None => "#ff3329".to_string(),
+            };
+            self.painter.draw_text(tx, 10.0, text, &color)?;
+        }
+        self.painter.set_hex_color("#666666")?;
+        for sx in separators.into_iter().filter(|sx| *sx < last_x) {
+            self.painter.cairo_conn.move_to(sx, 12.0);
+            self.painter
+                .cairo_conn
+                .line_to(sx, self.height as f64 - 12.0);
+            self.painter.cairo_conn.stroke()?;
+        }
+
+        *self.layout.borrow_mut() = placed
+            .iter()
+            .map(|(x, width, _, _, block)| Placed {
+                x: *x,
+                width: *width,
+                name: block.name.clone(),
+                instance: block.instance.clone(),
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
+        if let Event::MouseClick {
+            x,
+            y,
+            root_x,
+            root_y,
+            button,
+        } = event
+            && self.contains_point(*x, *y)
+        {
+            self.click(*x, *y, (*root_x, *root_y), *button);
+        }
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let Ok(shared) = self.shared.lock() else {
+            return Snapshot::default();
+        };
+        let text: Vec<String> = shared.blocks.iter().map(|block| block.text()).collect();
+        let blocks: Vec<_> = shared
+            .blocks
+            .iter()
+            .map(|block| {
+                json!({
+                    "name": block.name,
+                    "instance": block.instance,
+                    "text": block.text(),
+                    "urgent": block.urgent,
+                })
+            })
+            .collect();
+        Snapshot::new(text.join(" | "), json!({ "blocks": blocks }))
+    }
+
+    // 和 Script 一样，所有实例共用一个消息
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event {
+            let dirty = self.shared.lock().map(|s| s.dirty).unwrap_or(false);
+            if dirty && Message::from(ev.data()) == Message::I3barUpdate {
+                self.draw()?;
+                self.painter.flush()?;
+            }
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,118 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use xcb::x;
+
+use super::Painter;
+use super::painter::icon_surface;
+use crate::error::{MyBarError, MyResult};
+use crate::x11::{WindowTracker, icon};
+
+// 没有 _NET_WM_ICON 时按 WM_CLASS 选用的 Nerd Font 图标
+const CLASS_GLYPHS: &[(&str, &str)] = &[
+    ("firefox", "\u{f269}"),
+    ("chromium", "\u{f268}"),
+    ("google-chrome", "\u{f268}"),
+    ("alacritty", "\u{f120}"),
+    ("kitty", "\u{f120}"),
+    ("xterm", "\u{f120}"),
+    ("code", "\u{f121}"),
+    ("thunar", "\u{f07b}"),
+    ("telegramdesktop", "\u{f2c6}"),
+];
+const DEFAULT_GLYPH: &str = "\u{f2d0}";
+
+/// 窗口图标：优先用 _NET_WM_ICON，没有时用按 WM_CLASS 映射的字形
+enum WindowIcon {
+    Image(cairo::ImageSurface),
+    Glyph(&'static str),
+}
+
+fn class_glyph(class: &str) -> &'static str {
+    let class = class.to_lowercase();
+    CLASS_GLYPHS
+        .iter()
+        .find(|(name, _)| *name == class)
+        .map(|(_, glyph)| *glyph)
+        .unwrap_or(DEFAULT_GLYPH)
+}
+
+/// 按窗口缓存图标和 WM_CLASS，每个窗口只向 X 查询一次
+pub struct IconCache<'a> {
+    conn: &'a xcb::Connection,
+    icon_atom: x::Atom,
+    size: f64,
+    icons: RefCell<HashMap<x::Window, WindowIcon>>,
+    classes: RefCell<HashMap<x::Window, Option<String>>>,
+}
+
+impl<'a> IconCache<'a> {
+    pub fn new(tracker: &WindowTracker<'a>, size: f64) -> Self {
+        Self {
+            conn: tracker.conn(),
+            icon_atom: tracker.ewmh().atoms._NET_WM_ICON,
+            size,
+            icons: RefCell::new(HashMap::new()),
+            classes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// 图标的边长
+    pub fn size(&self) -> f64 {
+        self.size
+    }
+
+    /// 窗口的 WM_CLASS（class 部分）
+    pub fn class(&self, window: x::Window) -> Option<String> {
+        let mut classes = self.classes.borrow_mut();
+        classes
+            .entry(window)
+            .or_insert_with(|| match icon::get_wm_class(self.conn, window) {
+                Ok(class) => class.map(|(_, class)| class),
+                Err(e) => {
+                    eprintln!("get WM_CLASS of {window:?}: {e}");
+                    None
+                }
+            })
+            .clone()
+    }
+
+    fn load(&self, window: x::Window) -> MyResult<WindowIcon> {
+        if let Some(icon) = icon::get_wm_icon(self.conn, self.icon_atom, window, self.size as u32)?
+        {
+            return Ok(WindowIcon::Image(icon_surface(&icon)?));
+        }
+        let glyph = match self.class(window) {
+            Some(class) => class_glyph(&class),
+            None => DEFAULT_GLYPH,
+        };
+        Ok(WindowIcon::Glyph(glyph))
+    }
+
+    /// 在 x 处画窗口图标，字形图标使用 color
+    pub fn draw(
+        &self,
+        painter: &Painter,
+        window: x::Window,
+        x: f64,
+        color: &str,
+    ) -> Result<(), MyBarError> {
+        let mut icons = self.icons.borrow_mut();
+        let icon = icons.entry(window).or_insert_with(|| {
+            self.load(window).unwrap_or_else(|e| {
+                eprintln!("load icon of {window:?}: {e}");
+                WindowIcon::Glyph(DEFAULT_GLYPH)
+            })
+        });
+        match icon {
+            WindowIcon::Image(image) => painter.draw_image(image, x, self.size),
+            WindowIcon::Glyph(glyph) => painter.draw_text(x, 10.0, glyph, color),
+        }
+    }
+
+    /// 窗口销毁后清掉它的缓存
+    pub fn forget(&self, window: x::Window) {
+        self.icons.borrow_mut().remove(&window);
+        self.classes.borrow_mut().remove(&window);
+    }
+}
@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+use super::{Component, Event, Painter, Snapshot};
+use crate::error::MyBarError;
+use crate::lemonbar::{self, Action, Align, Segment};
+use crate::message::Message;
+use xcb::x;
+
+/// 从 stdin 读取 lemonbar 格式的行并显示最新的一行
+///
+/// 和 lemonbar 一样，点击区域被点击时把命令输出到 stdout，通常接一个 `| sh` 执行。
+pub struct Lemonbar<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    segments: Arc<Mutex<Vec<Segment>>>,
+    // 每段文字的位置和它所在的点击区域
+    areas: RefCell<Vec<(f64, f64, Vec<Action>)>>,
+}
+
+impl<'a> Lemonbar<'a> {
+    pub fn new(painter: &'a Painter) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 40,
+            painter,
+            segments: Arc::new(Mutex::new(vec![])),
+            areas: RefCell::new(vec![]),
+        }
+    }
+
+    pub fn with_position(mut self, x: i16, width: u16) -> Self {
+        self.x = x;
+        self.width = width;
+        self
+    }
+
+    /// 启动读取 stdin 的线程；stdin 关闭后保留最后一行
+    pub fn start(self, conn: &Arc<xcb::Connection>, window: x::Window) -> Self {
+        let conn = Arc::clone(conn);
+        let segments = Arc::clone(&self.segments);
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        eprintln!("lemonbar: read stdin: {e}");
+                        break;
+                    }
+                };
+                if let Ok(mut segments) = segments.lock() {
+                    *segments = lemonbar::parse(&line);
+                }
+                if let Err(e) = Message::LemonbarUpdate.send(&conn, window) {
+                    eprintln!("lemonbar: send message: {e}");
+                }
+            }
+        });
+        self
+    }
+
+    fn click(&self, x: i16, button: u8) -> Result<(), MyBarError> {
+        let areas = self.areas.borrow();
+        let x = x as f64;
+        let Some((_, _, actions)) = areas.iter().find(|(ax, aw, _)| x >= *ax && x < ax + aw) else {
+            return Ok(());
+        };
+        // 嵌套的区域里最里层的优先
+        if let Some(action) = actions.iter().rev().find(|a| a.button == button) {
+            let mut stdout = std::io::stdout().lock();
+            writeln!(stdout, "{}", action.command)?;
+            stdout.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Component for Lemonbar<'_> {
+    fn name(&self) -> &str {
+        "lemonbar"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let segments = match self.segments.lock() {
+            Ok(segments) => segments.clone(),
+            Err(_) => return Ok(()),
+        };
+        let left = self.x as f64;
+        let right = left + self.width as f64;
+        self.painter.clear_area(left, self.width as f64)?;
+
+        let mut areas = vec![];
+        for align in [Align::Left, Align::Center, Align::Right] {
+            let group: Vec<&Segment> = segments.iter().filter(|s| s.style.align == align).collect();
+            if group.is_empty() {
+                continue;
+            }
+            let mut widths = vec![];
+            for segment in &group {
+                widths.push(self.painter.text_width(&segment.text)?);
+            }
+            let w: f64 = widths.iter().sum::<f64>() + 10.0 * 2.0;
+            let start = match align {
+                Align::Left => left,
+                Align::Center => left + (self.width as f64 - w) / 2.0,
+                Align::Right => right - w,
+            };
+            self.painter
+                .draw_rounded_background(start, w, 10.0, "#475164")?;
+
+            let mut x = start + 10.0;
+            for (segment, tw) in group.iter().zip(widths) {
+                let style = &segment.style;
+                if let Some(background) = &style.background {
+                    self.painter.draw_rectangle(x, tw, background)?;
+                }
+                let color = style.foreground.as_deref().unwrap_or("#ff3329");
+                self.painter.draw_text(x, 10.0, &segment.text, color)?;
+                if style.underline {
+                    let y = self.height as f64 - 7.0;
+                    self.painter
+                        .set_hex_color(style.underline_color.as_deref().unwrap_or(color))?;
+                    self.painter.cairo_conn.move_to(x, y);
+                    self.painter.cairo_conn.line_to(x + tw, y);
+                    self.painter.cairo_conn.stroke()?;
+                }
+                if !style.actions.is_empty() {
+                    areas.push((x, tw, style.actions.clone()));
+                }
+                x += tw;
+            }
+        }
+        *self.areas.borrow_mut() = areas;
+        Ok(())
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
+        if let Event::MouseClick { x, y, button, .. } = event
+            && self.contains_point(*x, *y)
+        {
+            self.click(*x, *button)?;
+        }
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let Ok(segments) = self.segments.lock() else {
+            return Snapshot::default();
+        };
+        Snapshot::new(
+            segments.iter().map(|s| s.text.as_str()).collect::<String>(),
+            serde_json::Value::Null,
+        )
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event
+            && Message::from(ev.data()) == Message::LemonbarUpdate
+        {
+            self.draw()?;
+            self.painter.flush()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
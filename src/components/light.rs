@@ -1,6 +1,19 @@
-use super::{Component, Event, Painter};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use super::tooltip::Hover;
+use super::{Component, Event, Painter, Snapshot};
 use crate::error::MyBarError;
-use crate::light;
+use crate::light::{self, LightDisplay};
+use crate::message::Message;
+use serde_json::json;
+use xcb::x;
+
+const ICON: &str = "";
+// 每台显示器的小亮度条之间的间距
+const GAP: f64 = 6.0;
 
 pub struct Light<'a> {
     x: i16,
@@ -8,72 +21,233 @@ pub struct Light<'a> {
     width: u16,
     height: u16,
     painter: &'a Painter<'a>,
+    displays: RefCell<Vec<LightDisplay>>,
+    // 后台线程枚举显示器的结果，收到之前没有显示器
+    enumerated: Option<Receiver<Vec<LightDisplay>>>,
+    // 发给后台线程写入的（显示器下标，亮度值）
+    writes: Option<Sender<(usize, u16)>>,
+    // 同步模式：滚动时所有显示器一起调节
+    linked: bool,
+    // 只显示指定的显示器（型号或序列号），例如状态栏所在的那台
+    only: Option<String>,
+    // 上次绘制时亮度条的起点和背景的右边界，鼠标的命中测试按这个计算
+    drawn: Cell<(f64, f64)>,
+    // 鼠标下的显示器，用来显示它的型号或序列号
+    hovered: Cell<Option<usize>>,
+    hover: Option<Hover<'a>>,
 }
 
 impl<'a> Light<'a> {
     pub fn new(painter: &'a Painter) -> Self {
         Self {
-            x: 1120,
+            x: 750,
             y: 0,
             width: 100,
             height: 40,
             painter,
+            displays: RefCell::new(vec![]),
+            enumerated: None,
+            writes: None,
+            linked: false,
+            only: None,
+            drawn: Cell::new((0.0, 0.0)),
+            hovered: Cell::new(None),
+            hover: None,
+        }
+    }
+
+    pub fn with_linked(mut self, linked: bool) -> Self {
+        self.linked = linked;
+        self
+    }
+
+    pub fn with_only(mut self, name: &str) -> Self {
+        self.only = Some(name.to_string());
+        self
+    }
+
+    /// 鼠标悬停时弹出显示器的型号或序列号和亮度
+    pub fn with_tooltips(
+        mut self,
+        conn: &'a xcb::Connection,
+        bar: x::Window,
+        visual: x::Visualtype,
+    ) -> Self {
+        self.hover = Some(Hover::new(conn, bar, visual));
+        self
+    }
+
+    /// DDC 枚举每台显示器要几十到几百毫秒，放到后台线程里，完成后用 Message::LightUpdate 通知重画。
+    /// 之后滚轮调节的亮度也由这个线程写入
+    pub fn start(mut self, conn: &Arc<xcb::Connection>, window: x::Window) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (writes, requests) = mpsc::channel::<(usize, u16)>();
+        let conn = Arc::clone(conn);
+        std::thread::spawn(move || {
+            let (displays, mut handles) = light::enumerate();
+            if sender.send(displays).is_ok()
+                && let Err(e) = Message::LightUpdate.send(&conn, window)
+            {
+                eprintln!("light: send message: {e}");
+            }
+            // 组件 drop 后 recv 返回错误，线程退出
+            while let Ok((index, value)) = requests.recv() {
+                // 滚得快时每台显示器只写最后一次的值
+                let mut pending = BTreeMap::from([(index, value)]);
+                pending.extend(requests.try_iter());
+                for (index, value) in pending {
+                    if let Err(e) = handles.write(index, value) {
+                        eprintln!("{e}");
+                    }
+                }
+            }
+        });
+        self.enumerated = Some(receiver);
+        self.writes = Some(writes);
+        self
+    }
+
+    // 参与显示的显示器下标
+    fn visible(&self, displays: &[LightDisplay]) -> Vec<usize> {
+        displays
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| self.only.as_deref().is_none_or(|name| d.matches(name)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn icon_width(&self) -> Result<f64, MyBarError> {
+        self.painter.text_width(ICON)
+    }
+
+    // 每台显示器小亮度条的宽度
+    fn segment_width(&self, count: usize) -> f64 {
+        let count = count.max(1) as f64;
+        (self.width as f64 - GAP * (count - 1.0)) / count
+    }
+
+    // 鼠标 x 坐标下的显示器
+    fn hovered(&self, x: i16, visible: &[usize]) -> Option<usize> {
+        let (start, _) = self.drawn.get();
+        let sw = self.segment_width(visible.len());
+        let offset = x as f64 - start;
+        if offset < 0.0 {
+            return visible.first().copied();
+        }
+        let i = ((offset / (sw + GAP)) as usize).min(visible.len().saturating_sub(1));
+        visible.get(i).copied()
+    }
+
+    fn step(&self, x: i16, delta: i32) -> Result<(), MyBarError> {
+        let mut displays = self.displays.borrow_mut();
+        let visible = self.visible(&displays);
+        let Some(base) = self.hovered(x, &visible) else {
+            return Ok(());
+        };
+        // 同步模式以鼠标下的显示器为基准，所有显示器设置成同一个值
+        let targets = if self.linked { visible } else { vec![base] };
+        let v = (displays[base].percent() as i32 + delta).clamp(0, 100) as u16;
+        for i in targets {
+            let value = displays[i].set_percent(v);
+            if let Some(writes) = &self.writes
+                && writes.send((i, value)).is_err()
+            {
+                eprintln!("light: brightness thread exited");
+            }
         }
+        Ok(())
+    }
+
+    fn update_tooltip(&self) -> Result<(), MyBarError> {
+        let Some(hover) = &self.hover else {
+            return Ok(());
+        };
+        let displays = self.displays.borrow();
+        let text = self
+            .hovered
+            .get()
+            .and_then(|i| displays.get(i))
+            .map(|d| format!("{}  {}%", d.label(), d.percent()));
+        hover.show(self.painter, self.x, text.as_deref())
     }
 }
 
 impl<'a> Component for Light<'a> {
+    fn name(&self) -> &str {
+        "light"
+    }
+
+    fn close_popups(&self) {
+        self.hovered.set(None);
+        if let Some(hover) = &self.hover {
+            hover.close();
+        }
+    }
+
     fn draw(&self) -> Result<(), MyBarError> {
-        let brightness = light::get_light() as f64 / 100.0;
-        let icon = "";
+        let displays = self.displays.borrow();
+        let visible = self.visible(&displays);
+        let icon = ICON;
         let color = "#ffcc00";
-        let te = self.painter.text_width(&icon)?;
+        let te = self.icon_width()?;
         let iw = te + self.width as f64 + 5.0;
 
         self.painter
             .draw_rounded_background(self.x as f64, iw + 10.0 * 2., 10.0, "#475164")?;
         self.painter
-            .draw_text(self.x as f64 + 10.0, 10.0, &icon, color)?;
+            .draw_text(self.x as f64 + 10.0, 10.0, icon, color)?;
 
-        let rw = self.width as f64 * brightness;
-        self.painter.set_hex_color(&color)?;
-        self.painter
-            .cairo_conn
-            .move_to(self.x as f64 + 10.0 + te + 5.0, 20.0);
-        self.painter
-            .cairo_conn
-            .line_to(self.x as f64 + 10.0 + te + 5.0 + rw, 20.0);
-        self.painter.cairo_conn.stroke()?;
+        let sw = self.segment_width(visible.len());
+        let mut x_offset = self.x as f64 + 10.0 + te + 5.0;
+        self.drawn.set((x_offset, self.x as f64 + iw + 10.0 * 2.));
+        self.painter.set_hex_color(color)?;
+        for i in visible {
+            let rw = sw * displays[i].percent() as f64 / 100.0;
+            self.painter.cairo_conn.move_to(x_offset, 20.0);
+            self.painter.cairo_conn.line_to(x_offset + rw, 20.0);
+            self.painter.cairo_conn.stroke()?;
+            x_offset += sw + GAP;
+        }
         Ok(())
     }
 
     fn contains_point(&self, x: i16, y: i16) -> bool {
         x >= self.x
-            && x <= self.x + self.width as i16
+            && (x as f64) <= self.drawn.get().1
             && y >= self.y
             && y <= self.y + self.height as i16
     }
 
     fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
         match event {
-            Event::MouseClick { x, y, button } => {
+            Event::MouseClick { x, button, .. } => {
                 let button = *button;
                 if button == 4 {
                     // 滚轮上
-                    let current_brightness = light::get_light();
-                    light::set_light((current_brightness + 5).min(100));
+                    self.step(*x, 5)?;
                     self.draw()?;
                 } else if button == 5 {
                     // 滚轮下
-                    let current_brightness = light::get_light();
-                    light::set_light((current_brightness as i32 - 5).max(0) as u16);
+                    self.step(*x, -5)?;
                     self.draw()?;
                 }
             }
-            Event::KeyPress { keycode } => {
+            Event::MouseMove { x, y } => {
+                let hovered = if self.contains_point(*x, *y) {
+                    let displays = self.displays.borrow();
+                    self.hovered(*x, &self.visible(&displays))
+                } else {
+                    None
+                };
+                self.hovered.set(hovered);
+            }
+            Event::MouseLeave => self.hovered.set(None),
+            Event::KeyPress { .. } => {
                 // TODO: 实现键盘控制亮度逻辑
             }
         }
+        self.update_tooltip()?;
         self.painter.flush()?;
         Ok(())
     }
@@ -81,4 +255,68 @@ impl<'a> Component for Light<'a> {
     fn get_bounds(&self) -> (i16, i16, u16, u16) {
         (self.x, self.y, self.width, self.height)
     }
-} 
\ No newline at end of file
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let Some(hover) = &self.hover
+            && hover.handle_x_event(event)?
+        {
+            return Ok(true);
+        }
+        let xcb::Event::X(x::Event::ClientMessage(ev)) = event else {
+            return Ok(false);
+        };
+        if Message::from(ev.data()) != Message::LightUpdate {
+            return Ok(false);
+        }
+        if let Some(displays) = self.enumerated.as_ref().and_then(|r| r.try_recv().ok()) {
+            *self.displays.borrow_mut() = displays;
+            self.draw()?;
+            self.painter.flush()?;
+        }
+        Ok(true)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let displays = self.displays.borrow();
+        let visible = self.visible(&displays);
+        let text: Vec<String> = visible
+            .iter()
+            .map(|&i| format!("{}%", displays[i].percent()))
+            .collect();
+        let state: Vec<_> = visible
+            .iter()
+            .map(|&i| json!({ "display": displays[i].label(), "percent": displays[i].percent() }))
+            .collect();
+        Snapshot::new(text.join(" "), json!(state))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GAP, Light, Painter};
+    use crate::error::MyResult;
+
+    #[test]
+    fn hover_segments() -> MyResult<()> {
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 1920, 40)?;
+        let painter = Painter::for_surface(&surface, 1920, 40)?;
+        let light = Light::new(&painter);
+        // 宽 100，三台显示器之间有两个间距
+        assert_eq!(light.segment_width(1), 100.0);
+        assert_eq!(light.segment_width(0), 100.0);
+        let sw = light.segment_width(3);
+        assert_eq!(sw, (100.0 - GAP * 2.0) / 3.0);
+
+        light.drawn.set((800.0, 920.0));
+        let visible = [0, 2, 3];
+        assert_eq!(light.hovered(790, &visible), Some(0));
+        assert_eq!(light.hovered(801, &visible), Some(0));
+        assert_eq!(
+            light.hovered((800.0 + sw + GAP + 1.0) as i16, &visible),
+            Some(2)
+        );
+        assert_eq!(light.hovered(915, &visible), Some(3));
+        assert_eq!(light.hovered(801, &[]), None);
+        Ok(())
+    }
+}
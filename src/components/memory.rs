@@ -0,0 +1,310 @@
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use super::{Component, Event, Painter, Snapshot};
+use crate::error::{MyBarError, MyResult};
+use crate::memory::{self, MemInfo, MemorySource, ZramStats};
+use crate::message::Message;
+use crate::scheduler::Scheduler;
+use serde_json::json;
+use xcb::x;
+
+const ICON: &str = "\u{f035b}";
+const SWAP_ICON: &str = "\u{f0ec}";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+const COLOR: &str = "#ff3329";
+// 使用率超过这个值时显示警告色
+const HIGH_RATIO: f64 = 0.9;
+// 横线模式下每条线满格时的长度
+const BAR_WIDTH: f64 = 60.0;
+
+/// 内存的显示方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryFormat {
+    /// "42%"
+    Percent,
+    /// "6.7/15.5G"
+    Absolute,
+    /// 和 Volume 一样的一条横线
+    Bar,
+}
+
+impl MemoryFormat {
+    /// 命令行里的名字：percent、absolute 或 bar
+    pub fn parse(name: &str) -> MyResult<Self> {
+        match name {
+            "percent" => Ok(MemoryFormat::Percent),
+            "absolute" => Ok(MemoryFormat::Absolute),
+            "bar" => Ok(MemoryFormat::Bar),
+            _ => Err(MyBarError::Other(format!("unknown memory format {name:?}"))),
+        }
+    }
+}
+
+pub struct Memory<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    source: MemorySource,
+    format: MemoryFormat,
+    // 是否显示 zram 的压缩率
+    zram: bool,
+    info: RefCell<MemInfo>,
+    zram_stats: RefCell<Option<ZramStats>>,
+    ticker: Option<timer::Guard>,
+    drawn_width: Cell<f64>,
+}
+
+impl<'a> Memory<'a> {
+    pub fn new(painter: &'a Painter, source: MemorySource) -> Self {
+        Self {
+            x: 1040,
+            y: 0,
+            width: 100,
+            height: 40,
+            painter,
+            source,
+            format: MemoryFormat::Percent,
+            zram: false,
+            info: RefCell::new(MemInfo::default()),
+            zram_stats: RefCell::new(None),
+            ticker: None,
+            drawn_width: Cell::new(0.0),
+        }
+    }
+
+    pub fn with_format(mut self, format: MemoryFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_zram(mut self, zram: bool) -> Self {
+        self.zram = zram;
+        self
+    }
+
+    pub fn with_refresh(mut self, scheduler: &Scheduler) -> Self {
+        self.ticker = Some(scheduler.every(REFRESH_INTERVAL, Message::MemoryUpdate));
+        self
+    }
+
+    fn refresh(&self) {
+        match self.source.meminfo() {
+            Ok(info) => *self.info.borrow_mut() = info,
+            Err(e) => eprintln!("read meminfo: {e}"),
+        }
+        if self.zram {
+            match self.source.zram() {
+                Ok(stats) => *self.zram_stats.borrow_mut() = stats,
+                Err(e) => eprintln!("read zram stats: {e}"),
+            }
+        }
+    }
+
+    fn text(&self, used: u64, total: u64, ratio: f64) -> String {
+        match self.format {
+            MemoryFormat::Absolute => format!(
+                "{:.1}/{:.1}G",
+                memory::kib_to_gib(used),
+                memory::kib_to_gib(total)
+            ),
+            _ => format!("{:.0}%", ratio * 100.0),
+        }
+    }
+
+    // 一段 图标 + 文字（或横线）的宽度
+    fn part_width(&self, icon: &str, text: &str) -> Result<f64, MyBarError> {
+        let content = match self.format {
+            MemoryFormat::Bar => BAR_WIDTH,
+            _ => self.painter.text_width(text)?,
+        };
+        Ok(self.painter.text_width(icon)? + 5.0 + content)
+    }
+
+    // 画一段 图标 + 文字（或横线），返回结束位置
+    fn draw_part(&self, x: f64, icon: &str, text: &str, ratio: f64) -> Result<f64, MyBarError> {
+        let color = if ratio >= HIGH_RATIO {
+            "#ff0000"
+        } else {
+            COLOR
+        };
+        self.painter.draw_text(x, 10.0, icon, color)?;
+        let x = x + self.painter.text_width(icon)? + 5.0;
+        if self.format != MemoryFormat::Bar {
+            self.painter.draw_text(x, 10.0, text, color)?;
+            return Ok(x + self.painter.text_width(text)?);
+        }
+        let bar_width = BAR_WIDTH * ratio;
+        self.painter.set_hex_color(color)?;
+        self.painter.cairo_conn.move_to(x, 20.0);
+        self.painter.cairo_conn.line_to(x + bar_width, 20.0);
+        self.painter.cairo_conn.stroke()?;
+        Ok(x + BAR_WIDTH)
+    }
+
+    fn draw_parts(
+        &self,
+        parts: &[(&'static str, String, f64)],
+        zram: Option<&str>,
+    ) -> Result<(), MyBarError> {
+        let mut x = self.x as f64 + 10.0;
+        for (icon, text, ratio) in parts {
+            x = self.draw_part(x, icon, text, *ratio)? + 10.0;
+        }
+        if let Some(zram) = zram {
+            self.painter.draw_text(x, 10.0, zram, "#666666")?;
+        }
+        Ok(())
+    }
+
+    fn parts(&self) -> Vec<(&'static str, String, f64)> {
+        let info = *self.info.borrow();
+        let mut parts = vec![(
+            ICON,
+            self.text(info.used(), info.total, info.used_ratio()),
+            info.used_ratio(),
+        )];
+        // 没有启用 swap 时不显示
+        if info.swap_total > 0 {
+            parts.push((
+                SWAP_ICON,
+                self.text(info.swap_used(), info.swap_total, info.swap_ratio()),
+                info.swap_ratio(),
+            ));
+        }
+        parts
+    }
+}
+
+/// 两侧留白 10、段间距 10，按顺序放进 max 宽度时能放下的段数，第一段总是显示
+fn fitting(max: f64, widths: &[f64]) -> usize {
+    let mut w = 10.0 * 2.0 - 10.0;
+    let mut count = 0;
+    for width in widths {
+        w += 10.0 + width;
+        if count > 0 && w > max {
+            break;
+        }
+        count += 1;
+    }
+    count
+}
+
+impl Component for Memory<'_> {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        self.refresh();
+        let mut parts = self.parts();
+        let mut zram = self
+            .zram_stats
+            .borrow()
+            .filter(|stats| stats.used > 0)
+            .map(|stats| format!("{:.1}x", stats.ratio()));
+
+        // 先量出总宽度，背景要在文字之前画。
+        // 按 内存、swap、zram 的顺序放进组件的宽度，放不下的整段不显示
+        let mut widths = vec![];
+        for (icon, text, _) in &parts {
+            widths.push(self.part_width(icon, text)?);
+        }
+        if let Some(zram) = &zram {
+            widths.push(self.painter.text_width(zram)?);
+        }
+        let max = self.width as f64;
+        let shown = fitting(max, &widths);
+        if shown <= parts.len() {
+            zram = None;
+            parts.truncate(shown);
+        }
+        let shown = &widths[..shown];
+        let w = 10.0 * 2.0 + shown.iter().sum::<f64>() + 10.0 * (shown.len() - 1) as f64;
+        // 只有内存一段也放不下时截掉超出的部分
+        let w = w.min(max);
+
+        if self.drawn_width.get() > w {
+            self.painter
+                .clear_area(self.x as f64, self.drawn_width.get())?;
+        }
+        self.drawn_width.set(w);
+        self.painter
+            .draw_rounded_background(self.x as f64, w, 10.0, "#475164")?;
+        let cr = &self.painter.cairo_conn;
+        cr.save()?;
+        cr.rectangle(self.x as f64, 0.0, w, self.painter.height() as f64);
+        cr.clip();
+        let result = self.draw_parts(&parts, zram.as_deref());
+        cr.restore()?;
+        result
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, _event: &Event) -> Result<(), MyBarError> {
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let info = *self.info.borrow();
+        let parts = self.parts();
+        let text: Vec<&str> = parts.iter().map(|(_, text, _)| text.as_str()).collect();
+        Snapshot::new(
+            text.join(" "),
+            json!({
+                "used": info.used(),
+                "total": info.total,
+                "swap_used": info.swap_used(),
+                "swap_total": info.swap_total,
+            }),
+        )
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event
+            && Message::from(ev.data()) == Message::MemoryUpdate
+        {
+            self.draw()?;
+            self.painter.flush()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MemoryFormat, fitting};
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(
+            MemoryFormat::parse("absolute").unwrap(),
+            MemoryFormat::Absolute
+        );
+        assert_eq!(MemoryFormat::parse("bar").unwrap(), MemoryFormat::Bar);
+        assert!(MemoryFormat::parse("gib").is_err());
+    }
+
+    #[test]
+    fn drop_parts_that_do_not_fit() {
+        // 20 + 40 + 10 + 30 = 100 正好放下两段
+        assert_eq!(fitting(100.0, &[40.0, 30.0, 20.0]), 2);
+        assert_eq!(fitting(100.0, &[40.0, 31.0]), 1);
+        // 第一段放不下也要显示，由调用方截掉
+        assert_eq!(fitting(50.0, &[80.0, 10.0]), 1);
+        assert_eq!(fitting(200.0, &[40.0, 30.0, 20.0]), 3);
+    }
+}
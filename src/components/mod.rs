@@ -1,9 +1,30 @@
+pub mod battery;
 pub mod bspwm;
+pub mod calendar;
+pub mod cpu;
 pub mod date;
+pub mod disk;
+#[cfg(test)]
+mod golden;
+pub mod i3bar;
+pub mod icons;
+pub mod lemonbar;
 pub mod light;
+pub mod memory;
+pub mod network;
 pub mod painter;
+pub mod rewrite;
+pub mod script;
+pub mod tail;
+pub mod taskbar;
+pub mod temperature;
 pub mod title;
+pub mod tooltip;
+pub mod tray;
 pub mod volume;
+pub mod waybar;
+
+use serde::Serialize;
 
 use crate::error::MyBarError;
 
@@ -12,16 +33,92 @@ pub trait Component {
     fn contains_point(&self, x: i16, y: i16) -> bool;
     fn handle_event(&self, event: &Event) -> Result<(), MyBarError>;
     fn get_bounds(&self) -> (i16, i16, u16, u16); // x, y, width, height
+    /// 组件自己创建或关注的窗口（例如弹出的日历）上的 X 事件，返回 true 表示已被该组件处理
+    fn handle_x_event(&self, _event: &xcb::Event) -> Result<bool, MyBarError> {
+        Ok(false)
+    }
+    /// IPC 命令里用来指定组件的名字
+    fn name(&self) -> &str;
+    /// 栏被隐藏时关闭组件弹出的窗口（日历、提示等）
+    fn close_popups(&self) {}
+    /// IPC 的 update 命令：立即刷新数据并重画，默认只是重画
+    fn update(&self) -> Result<(), MyBarError> {
+        self.draw()
+    }
+    /// IPC 的 state 命令和 update 事件里组件当前显示的内容，应只读取已有的数据而不去重新采样
+    fn snapshot(&self) -> Snapshot {
+        Snapshot::default()
+    }
+    /// IPC 的 action 命令，默认把鼠标按键名当作点击组件中间
+    fn action(&self, name: &str) -> Result<(), MyBarError> {
+        let button = match name {
+            "left" => 1,
+            "middle" => 2,
+            "right" => 3,
+            "scroll-up" => 4,
+            "scroll-down" => 5,
+            _ => return Err(MyBarError::Other(format!("unknown action {name}"))),
+        };
+        let (x, y, width, height) = self.get_bounds();
+        let (x, y) = (x + (width / 2) as i16, y + (height / 2) as i16);
+        // 没有真正的鼠标位置，屏幕坐标按状态栏在屏幕左上角计算
+        self.handle_event(&Event::MouseClick {
+            x,
+            y,
+            root_x: x,
+            root_y: y,
+            button,
+        })
+    }
+}
+
+/// 组件显示的文字（不含图标）和组件特有的状态，例如音量和是否静音
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub text: String,
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub state: serde_json::Value,
+}
+
+impl Snapshot {
+    pub fn new(text: impl Into<String>, state: serde_json::Value) -> Self {
+        Self {
+            text: text.into(),
+            state,
+        }
+    }
 }
 
+/// MouseMove 和 MouseLeave 会发给所有组件，组件据此判断鼠标是否悬停在自己上面
 pub enum Event {
-    MouseClick { x: i16, y: i16, button: u8 },
+    /// x、y 是相对状态栏窗口的坐标，root_x、root_y 是屏幕坐标（例如 i3bar 协议的点击事件需要）
+    MouseClick {
+        x: i16,
+        y: i16,
+        root_x: i16,
+        root_y: i16,
+        button: u8,
+    },
     KeyPress { keycode: u8 },
+    MouseMove { x: i16, y: i16 },
+    MouseLeave,
 }
 
+pub use battery::Battery;
 pub use bspwm::BspwmComponent;
+pub use cpu::Cpu;
 pub use date::Date;
+pub use disk::Disk;
+pub use i3bar::I3bar;
+pub use lemonbar::Lemonbar;
 pub use light::Light;
+pub use memory::Memory;
+pub use network::Network;
 pub use painter::Painter;
+pub use script::Script;
+pub use tail::Tail;
+pub use taskbar::Taskbar;
+pub use temperature::Temperature;
 pub use title::Title;
+pub use tray::Tray;
 pub use volume::Volume;
@@ -0,0 +1,239 @@
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use super::{Component, Event, Painter, Snapshot};
+use crate::error::MyBarError;
+use crate::message::Message;
+use crate::network::{self, NetKind, NetSource, NetStatus};
+use crate::scheduler::Scheduler;
+use crate::util;
+use serde_json::json;
+use xcb::x;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+const COLOR: &str = "#ff3329";
+
+fn icon(kind: NetKind) -> &'static str {
+    match kind {
+        NetKind::Wired => "\u{f0200}",
+        NetKind::Wireless => "\u{f1eb}",
+        NetKind::Vpn => "\u{f023}",
+        NetKind::Disconnected => "\u{f127}",
+    }
+}
+
+pub struct Network<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    source: RefCell<NetSource>,
+    status: RefCell<Option<NetStatus>>,
+    // 左键在 IPv4 和 IPv6 地址之间切换
+    show_ipv6: Cell<bool>,
+    show_rates: bool,
+    ticker: Option<timer::Guard>,
+    drawn_width: Cell<f64>,
+}
+
+impl<'a> Network<'a> {
+    pub fn new(painter: &'a Painter, source: NetSource) -> Self {
+        Self {
+            x: 1575,
+            y: 0,
+            width: 140,
+            height: 40,
+            painter,
+            source: RefCell::new(source),
+            status: RefCell::new(None),
+            show_ipv6: Cell::new(false),
+            show_rates: true,
+            ticker: None,
+            drawn_width: Cell::new(0.0),
+        }
+    }
+
+    pub fn with_rates(mut self, show_rates: bool) -> Self {
+        self.show_rates = show_rates;
+        self
+    }
+
+    pub fn with_refresh(mut self, scheduler: &Scheduler) -> Self {
+        self.ticker = Some(scheduler.every(REFRESH_INTERVAL, Message::NetworkUpdate));
+        self
+    }
+
+    /// 直接显示这个状态而不等第一次读取，用于截图
+    pub fn with_status(self, status: NetStatus) -> Self {
+        *self.status.borrow_mut() = Some(status);
+        self
+    }
+
+    // 只在定时器触发时读取，速率按两次定时读取之间的间隔计算
+    fn refresh(&self) {
+        match self.source.borrow_mut().status() {
+            Ok(status) => *self.status.borrow_mut() = Some(status),
+            Err(e) => eprintln!("read network status: {e}"),
+        }
+    }
+
+    fn text(&self, status: &NetStatus) -> String {
+        self.fields(status).join(" ")
+    }
+
+    // 按重要程度排列：地址、信号质量、下载速率、上传速率
+    fn fields(&self, status: &NetStatus) -> Vec<String> {
+        if status.kind == NetKind::Disconnected {
+            return vec![status.interface.clone().unwrap_or_default()];
+        }
+        let address = if self.show_ipv6.get() {
+            status.ipv6.map(|a| a.to_string())
+        } else {
+            status.ipv4.map(|a| a.to_string())
+        };
+        let mut parts = vec![address.or(status.interface.clone()).unwrap_or_default()];
+        if let Some(quality) = status.quality {
+            parts.push(format!("{quality}%"));
+        }
+        if self.show_rates {
+            parts.push(format!("\u{2193}{}", network::format_rate(status.rx_rate)));
+            parts.push(format!("\u{2191}{}", network::format_rate(status.tx_rate)));
+        }
+        parts
+    }
+}
+
+/// 从前往后放进 max 宽度，放不下的项整项不显示；
+/// 第一项（地址）自己就放不下时（例如 IPv6）截掉中间
+fn fit_fields(fields: &[String], max: f64, measure: impl Fn(&str) -> f64) -> String {
+    let Some(first) = fields.first() else {
+        return String::new();
+    };
+    let mut text = util::ellipsize(first, max, true, &measure);
+    for field in &fields[1..] {
+        let longer = format!("{text} {field}");
+        if measure(&longer) > max {
+            break;
+        }
+        text = longer;
+    }
+    text
+}
+
+impl Component for Network<'_> {
+    fn name(&self) -> &str {
+        "network"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let status = self.status.borrow();
+        let (icon, fields, color) = match status.as_ref() {
+            Some(status) if status.kind != NetKind::Disconnected => {
+                (icon(status.kind), self.fields(status), COLOR)
+            }
+            Some(status) => (icon(NetKind::Disconnected), self.fields(status), "#666666"),
+            None => (icon(NetKind::Disconnected), vec![], "#666666"),
+        };
+        let iw = self.painter.text_width(icon)?;
+        let max = self.width as f64 - iw - 5.0 - 10.0 * 2.0;
+        let text = fit_fields(&fields, max, |s| {
+            self.painter.text_width(s).unwrap_or(f64::MAX)
+        });
+        let tw = self.painter.text_width(&text)?;
+        let w = iw + 5.0 + tw + 10.0 * 2.0;
+
+        if self.drawn_width.get() > w {
+            self.painter
+                .clear_area(self.x as f64, self.drawn_width.get())?;
+        }
+        self.drawn_width.set(w);
+        self.painter
+            .draw_rounded_background(self.x as f64, w, 10.0, "#475164")?;
+        self.painter
+            .draw_text(self.x as f64 + 10.0, 10.0, icon, color)?;
+        self.painter
+            .draw_text(self.x as f64 + 10.0 + iw + 5.0, 10.0, &text, color)?;
+        Ok(())
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
+        if let Event::MouseClick { x, y, button: 1, .. } = event
+            && self.contains_point(*x, *y)
+        {
+            self.show_ipv6.set(!self.show_ipv6.get());
+            self.draw()?;
+            self.painter.flush()?;
+        }
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let status = self.status.borrow();
+        let Some(status) = status.as_ref() else {
+            return Snapshot::default();
+        };
+        Snapshot::new(
+            self.text(status),
+            json!({
+                "interface": status.interface,
+                "kind": format!("{:?}", status.kind).to_lowercase(),
+                "up": status.up,
+                "ipv4": status.ipv4,
+                "ipv6": status.ipv6,
+                "rx_rate": status.rx_rate,
+                "tx_rate": status.tx_rate,
+                "quality": status.quality,
+            }),
+        )
+    }
+
+    fn update(&self) -> Result<(), MyBarError> {
+        self.refresh();
+        self.draw()
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event
+            && Message::from(ev.data()) == Message::NetworkUpdate
+        {
+            self.refresh();
+            self.draw()?;
+            self.painter.flush()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::fit_fields;
+
+    #[test]
+    fn drop_fields_that_do_not_fit() {
+        let fields = ["192.168.1.23", "72%", "↓1.2M", "↑48.0K"].map(String::from);
+        let measure = |s: &str| s.chars().count() as f64;
+        assert_eq!(
+            fit_fields(&fields, 40.0, measure),
+            "192.168.1.23 72% ↓1.2M ↑48.0K"
+        );
+        // 上传速率整项去掉，地址和信号质量不截断
+        assert_eq!(fit_fields(&fields, 25.0, measure), "192.168.1.23 72% ↓1.2M");
+        assert_eq!(fit_fields(&fields, 12.0, measure), "192.168.1.23");
+        // 只剩地址也放不下时截掉中间
+        assert_eq!(fit_fields(&fields, 8.0, measure).chars().count(), 8);
+        assert_eq!(fit_fields(&[], 8.0, measure), "");
+    }
+}
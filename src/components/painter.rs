@@ -3,11 +3,13 @@ use cairo;
 use xcb::{Xid, x};
 
 use crate::util;
+use crate::x11::icon::Icon;
 
 pub struct Painter<'a> {
     width: i32,
     height: i32,
-    conn: &'a xcb::Connection,
+    // 画在窗口上时需要 flush X 连接；画在图片上时为 None
+    conn: Option<&'a xcb::Connection>,
     pub cairo_conn: cairo::Context,
 }
 
@@ -20,6 +22,17 @@ impl<'a> Painter<'a> {
         height: i32,
     ) -> Result<Self, MyBarError> {
         let surface = create_surface(conn, window, visual_type, width, height)?;
+        let mut painter = Self::for_surface(&surface, width, height)?;
+        painter.conn = Some(conn);
+        Ok(painter)
+    }
+
+    /// 画在任意 cairo surface 上，例如无界面渲染时的 ImageSurface
+    pub fn for_surface(
+        surface: &cairo::Surface,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, MyBarError> {
         let cairo_conn = cairo::Context::new(surface)?;
         cairo_conn.select_font_face(
             "Maple Mono NL NF CN",
@@ -30,14 +43,18 @@ impl<'a> Painter<'a> {
         Ok(Painter {
             width,
             height,
-            conn,
+            conn: None,
             cairo_conn,
         })
     }
+
     pub fn flush(&self) -> Result<(), MyBarError> {
-        self.conn
-            .flush()
-            .map_err(|_| MyBarError::Other("Flush error".to_string()))?;
+        match self.conn {
+            Some(conn) => conn
+                .flush()
+                .map_err(|_| MyBarError::Other("Flush error".to_string()))?,
+            None => self.cairo_conn.target().flush(),
+        }
         Ok(())
     }
     pub fn text_width(&self, text: &str) -> Result<f64, MyBarError> {
@@ -61,14 +78,67 @@ impl<'a> Painter<'a> {
     }
 
     pub fn draw_text(&self, x: f64, y: f64, text: &str, color: &str) -> Result<(), MyBarError> {
+        self.draw_text_at(x, self.height as f64 / 2.0, text, color)
+    }
+
+    /// 以 center_y 为垂直中心绘制一行文字，用于多行布局（例如日历网格）
+    pub fn draw_text_at(
+        &self,
+        x: f64,
+        center_y: f64,
+        text: &str,
+        color: &str,
+    ) -> Result<(), MyBarError> {
         self.set_hex_color(color)?;
         let fe = self.cairo_conn.font_extents()?;
-        let y = self.height as f64 / 2.0 + (fe.ascent() - fe.descent()) / 2.0;
+        let y = center_y + (fe.ascent() - fe.descent()) / 2.0;
         self.cairo_conn.move_to(x, y);
         self.cairo_conn.show_text(text)?;
         Ok(())
     }
 
+    /// 状态栏的高度
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// 把一段区域清成透明，用于组件内容变短时擦掉上一次的残留
+    pub fn clear_area(&self, x: f64, width: f64) -> Result<(), MyBarError> {
+        self.cairo_conn.save()?;
+        self.cairo_conn.set_operator(cairo::Operator::Clear);
+        self.cairo_conn.rectangle(x, 0.0, width, self.height as f64);
+        self.cairo_conn.fill()?;
+        self.cairo_conn.restore()?;
+        Ok(())
+    }
+
+    /// 窗口图标的边长，取状态栏高度的一半
+    pub fn icon_size(&self) -> f64 {
+        (self.height / 2) as f64
+    }
+
+    /// 把图片等比缩放到能放进 size x size 的方框，在方框里居中，方框垂直居中放在 x 处
+    pub fn draw_image(
+        &self,
+        image: &cairo::ImageSurface,
+        x: f64,
+        size: f64,
+    ) -> Result<(), MyBarError> {
+        let scale = size / image.width().max(image.height()).max(1) as f64;
+        let width = image.width() as f64 * scale;
+        let height = image.height() as f64 * scale;
+        self.cairo_conn.save()?;
+        self.cairo_conn.translate(
+            x + (size - width) / 2.0,
+            (self.height as f64 - height) / 2.0,
+        );
+        self.cairo_conn.scale(scale, scale);
+        self.cairo_conn.set_source_surface(image, 0.0, 0.0)?;
+        self.cairo_conn.paint()?;
+        self.cairo_conn.restore()?;
+        Ok(())
+    }
+
     pub fn draw_rounded_background(
         &self,
         x: f64,
@@ -118,6 +188,30 @@ impl<'a> Painter<'a> {
     }
 }
 
+/// 把 _NET_WM_ICON 的 ARGB 像素转换成 cairo 图片（cairo 需要预乘 alpha）
+pub fn icon_surface(icon: &Icon) -> Result<cairo::ImageSurface, MyBarError> {
+    let format = cairo::Format::ARgb32;
+    let stride = format.stride_for_width(icon.width)?;
+    let mut data = vec![0u8; stride as usize * icon.height as usize];
+    for (row, pixels) in icon.pixels.chunks(icon.width as usize).enumerate() {
+        for (col, &argb) in pixels.iter().enumerate() {
+            let a = argb >> 24;
+            let premultiply = |shift: u32| ((argb >> shift) & 0xff) * a / 255;
+            let pixel =
+                (a << 24) | (premultiply(16) << 16) | (premultiply(8) << 8) | premultiply(0);
+            let offset = row * stride as usize + col * 4;
+            data[offset..offset + 4].copy_from_slice(&pixel.to_ne_bytes());
+        }
+    }
+    Ok(cairo::ImageSurface::create_for_data(
+        data,
+        format,
+        icon.width as i32,
+        icon.height as i32,
+        stride,
+    )?)
+}
+
 fn create_surface(
     conn: &xcb::Connection,
     window: xcb::x::Window,
@@ -0,0 +1,112 @@
+use regex::Regex;
+
+use crate::error::{MyBarError, MyResult};
+
+/// 标题改写规则
+///
+/// 指定了 class 的规则只作用于 WM_CLASS 相同（不区分大小写）的窗口，并且先于通用规则执行。
+/// 每条匹配的规则都会在上一条的结果上继续改写，replace 中可以用 `$1`、`${name}` 引用捕获组。
+pub struct TitleRule {
+    class: Option<String>,
+    pattern: Option<Regex>,
+    replace: String,
+    color: Option<String>,
+}
+
+impl TitleRule {
+    pub fn new(pattern: &str, replace: &str) -> MyResult<Self> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| MyBarError::Other(format!("invalid title rule {pattern}: {e}")))?;
+        Ok(Self {
+            class: None,
+            pattern: Some(pattern),
+            replace: replace.to_string(),
+            color: None,
+        })
+    }
+
+    /// 不改写标题，只给某个 WM_CLASS 指定颜色
+    pub fn class_color(class: &str, color: &str) -> Self {
+        Self {
+            class: Some(class.to_string()),
+            pattern: None,
+            replace: String::new(),
+            color: Some(color.to_string()),
+        }
+    }
+
+    pub fn for_class(mut self, class: &str) -> Self {
+        self.class = Some(class.to_string());
+        self
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = Some(color.to_string());
+        self
+    }
+
+    fn matches(&self, class: Option<&str>, title: &str) -> bool {
+        let class_matches = match (&self.class, class) {
+            (None, _) => true,
+            (Some(rule), Some(class)) => rule.eq_ignore_ascii_case(class),
+            (Some(_), None) => false,
+        };
+        class_matches && self.pattern.as_ref().is_none_or(|p| p.is_match(title))
+    }
+}
+
+/// 依次应用规则，返回改写后的标题和颜色（第一条指定了颜色的匹配规则）
+pub fn apply<'r>(
+    rules: &'r [TitleRule],
+    class: Option<&str>,
+    title: &str,
+) -> (String, Option<&'r str>) {
+    let ordered = rules
+        .iter()
+        .filter(|r| r.class.is_some())
+        .chain(rules.iter().filter(|r| r.class.is_none()));
+    let mut title = title.to_string();
+    let mut color = None;
+    for rule in ordered {
+        if !rule.matches(class, &title) {
+            continue;
+        }
+        if let Some(pattern) = &rule.pattern {
+            title = pattern.replace(&title, rule.replace.as_str()).into_owned();
+        }
+        if color.is_none() {
+            color = rule.color.as_deref();
+        }
+    }
+    (title, color)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TitleRule, apply};
+
+    #[test]
+    fn class_rules_before_generic_rules() {
+        let rules = vec![
+            TitleRule::new(r" — Mozilla Firefox$", "").unwrap(),
+            TitleRule::new(r"^\S+@\S+:\s*(?<cwd>.*)$", "\u{f120} ${cwd}")
+                .unwrap()
+                .for_class("Alacritty")
+                .with_color("#a6e3a1"),
+            TitleRule::class_color("firefox", "#fab387"),
+        ];
+
+        assert_eq!(
+            apply(&rules, Some("firefox"), "Rust — Mozilla Firefox"),
+            ("Rust".to_string(), Some("#fab387"))
+        );
+        assert_eq!(
+            apply(&rules, Some("alacritty"), "me@host: ~/code/mybar"),
+            ("\u{f120} ~/code/mybar".to_string(), Some("#a6e3a1"))
+        );
+        assert_eq!(
+            apply(&rules, None, "me@host: ~"),
+            ("me@host: ~".to_string(), None)
+        );
+    }
+}
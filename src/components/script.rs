@@ -0,0 +1,354 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::tooltip::Hover;
+use super::waybar::WaybarFormat;
+use super::{Component, Event, Painter, Snapshot};
+use crate::command::{self, CommandOutput};
+use crate::error::MyBarError;
+use crate::message::Message;
+use crate::waybar::WaybarOutput;
+use xcb::x;
+
+// i3blocks 的约定：退出码 33 表示需要引起注意
+const URGENT_EXIT_CODE: i32 = 33;
+
+/// 根据退出码决定模块的显示状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptState {
+    Normal,
+    Urgent,
+    /// 非零退出码（或超时）时隐藏模块
+    Hidden,
+}
+
+impl ScriptState {
+    fn from_output(output: &CommandOutput) -> Self {
+        match output.code {
+            _ if output.timed_out => ScriptState::Hidden,
+            Some(0) => ScriptState::Normal,
+            Some(URGENT_EXIT_CODE) => ScriptState::Urgent,
+            _ => ScriptState::Hidden,
+        }
+    }
+}
+
+// 后台线程写入、X 线程读取的结果
+#[derive(Default)]
+struct Shared {
+    output: WaybarOutput,
+    state: Option<ScriptState>,
+    // 有新结果还没画
+    dirty: bool,
+}
+
+impl Shared {
+    // 按退出码处理过的输出
+    fn shown(&self) -> WaybarOutput {
+        let mut output = self.output.clone();
+        match self.state {
+            Some(ScriptState::Normal) => {}
+            Some(ScriptState::Urgent) => output.class.insert(0, "urgent".to_string()),
+            // 还没有结果或者需要隐藏
+            _ => output = WaybarOutput::default(),
+        }
+        output
+    }
+}
+
+/// 定时运行一条命令，显示它 stdout 的第一行，或者按 Waybar 的 JSON 格式解析整个输出
+///
+/// 命令在后台线程里运行，结果通过 Message::ScriptUpdate 通知 X 线程重绘，
+/// 所以慢脚本不会阻塞绘制。
+pub struct Script<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    command: String,
+    name: String,
+    interval: Duration,
+    timeout: Duration,
+    // 鼠标按键对应的命令
+    clicks: HashMap<u8, String>,
+    json: bool,
+    format: WaybarFormat,
+    hover: Option<Hover<'a>>,
+    hovered: Cell<bool>,
+    shared: Arc<Mutex<Shared>>,
+    // 通知后台线程立即重新运行；drop 时后台线程随之退出
+    trigger: Option<Sender<()>>,
+    drawn_width: Cell<f64>,
+}
+
+impl<'a> Script<'a> {
+    pub fn new(painter: &'a Painter, command: &str) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: 150,
+            height: 40,
+            painter,
+            command: command.to_string(),
+            name: command::program_name(command),
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(5),
+            clicks: HashMap::new(),
+            json: false,
+            format: WaybarFormat::new(),
+            hover: None,
+            hovered: Cell::new(false),
+            shared: Arc::new(Mutex::new(Shared::default())),
+            trigger: None,
+            drawn_width: Cell::new(0.0),
+        }
+    }
+
+    /// IPC 命令里用的名字，默认是命令的程序名
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// 同一种组件可能有多个实例，所以位置可以配置
+    pub fn with_position(mut self, x: i16, width: u16) -> Self {
+        self.x = x;
+        self.width = width;
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 点击 button 时在后台运行 command，运行结束后立即刷新一次
+    pub fn with_click(mut self, button: u8, command: &str) -> Self {
+        self.clicks.insert(button, command.to_string());
+        self
+    }
+
+    /// 把输出当作 Waybar 的 JSON 对象解析，按 format 绘制
+    pub fn with_json(mut self, format: WaybarFormat) -> Self {
+        self.json = true;
+        self.format = format;
+        self
+    }
+
+    /// 鼠标悬停时弹出输出里的 tooltip
+    pub fn with_tooltips(
+        mut self,
+        conn: &'a xcb::Connection,
+        bar: x::Window,
+        visual: x::Visualtype,
+    ) -> Self {
+        self.hover = Some(Hover::new(conn, bar, visual));
+        self
+    }
+
+    /// 启动后台线程，之后每隔 interval 运行一次命令
+    pub fn start(mut self, conn: &Arc<xcb::Connection>, window: x::Window) -> Self {
+        let (trigger, triggered) = mpsc::channel::<()>();
+        let conn = Arc::clone(conn);
+        let shared = Arc::clone(&self.shared);
+        let command = self.command.clone();
+        let (interval, timeout, json) = (self.interval, self.timeout, self.json);
+        std::thread::spawn(move || {
+            loop {
+                match command::run(&command, timeout) {
+                    Ok(output) => {
+                        if let Ok(mut shared) = shared.lock() {
+                            shared.output = parse(&output, json);
+                            shared.state = Some(ScriptState::from_output(&output));
+                            shared.dirty = true;
+                        }
+                        if let Err(e) = Message::ScriptUpdate.send(&conn, window) {
+                            eprintln!("script: send message: {e}");
+                        }
+                    }
+                    Err(e) => eprintln!("run {command}: {e}"),
+                }
+                match triggered.recv_timeout(interval) {
+                    Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+        self.trigger = Some(trigger);
+        self
+    }
+
+    fn click(&self, button: u8) {
+        let Some(click) = self.clicks.get(&button).cloned() else {
+            return;
+        };
+        let trigger = self.trigger.clone();
+        // 等点击命令结束再刷新，这样能显示它造成的变化
+        std::thread::spawn(move || {
+            if let Err(e) = command::run(&click, Duration::from_secs(30)) {
+                eprintln!("run {click}: {e}");
+            }
+            if let Some(trigger) = trigger {
+                let _ = trigger.send(());
+            }
+        });
+    }
+
+    // 鼠标在已绘制的部分上时显示当前输出的 tooltip，否则关闭
+    fn update_tooltip(&self) -> Result<(), MyBarError> {
+        let Some(hover) = &self.hover else {
+            return Ok(());
+        };
+        let tooltip = match self.shared.lock() {
+            Ok(shared) if self.hovered.get() && self.drawn_width.get() > 0.0 => {
+                shared.output.tooltip.clone()
+            }
+            _ => None,
+        };
+        hover.show(self.painter, self.x, tooltip.as_deref())
+    }
+}
+
+fn parse(output: &CommandOutput, json: bool) -> WaybarOutput {
+    if !json {
+        return WaybarOutput::plain(output.first_line());
+    }
+    WaybarOutput::parse(&output.stdout).unwrap_or_else(|e| {
+        eprintln!("script: invalid json output: {e}");
+        WaybarOutput::plain(output.first_line())
+    })
+}
+
+impl Component for Script<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn close_popups(&self) {
+        self.hovered.set(false);
+        if let Some(hover) = &self.hover {
+            hover.close();
+        }
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let output = match self.shared.lock() {
+            Ok(mut shared) => {
+                shared.dirty = false;
+                shared.shown()
+            }
+            Err(_) => return Ok(()),
+        };
+        self.format.draw(
+            self.painter,
+            self.x as f64,
+            self.width as f64,
+            &output,
+            &self.drawn_width,
+        )
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
+        match event {
+            Event::MouseClick { x, y, button, .. } if self.contains_point(*x, *y) => {
+                self.click(*button);
+            }
+            Event::MouseMove { x, y } => {
+                let inside =
+                    self.contains_point(*x, *y) && ((*x - self.x) as f64) < self.drawn_width.get();
+                self.hovered.set(inside);
+                self.update_tooltip()?;
+            }
+            Event::MouseLeave => {
+                self.hovered.set(false);
+                self.update_tooltip()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        match self.shared.lock() {
+            Ok(shared) => shared.shown().into(),
+            Err(_) => Snapshot::default(),
+        }
+    }
+
+    // 在后台重新运行命令，结果回来后照常重画
+    fn update(&self) -> Result<(), MyBarError> {
+        if let Some(trigger) = &self.trigger {
+            let _ = trigger.send(());
+        }
+        Ok(())
+    }
+
+    // 所有 Script 实例共用一个消息，只重画有新结果的那个，并且不拦截消息
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let Some(hover) = &self.hover
+            && hover.handle_x_event(event)?
+        {
+            return Ok(true);
+        }
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event {
+            let dirty = self.shared.lock().map(|s| s.dirty).unwrap_or(false);
+            if dirty && Message::from(ev.data()) == Message::ScriptUpdate {
+                self.draw()?;
+                self.update_tooltip()?;
+                self.painter.flush()?;
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ScriptState;
+    use crate::command::CommandOutput;
+
+    #[test]
+    fn state_from_exit_code() {
+        let output = |code, timed_out| CommandOutput {
+            stdout: String::new(),
+            code,
+            timed_out,
+        };
+        assert_eq!(
+            ScriptState::from_output(&output(Some(0), false)),
+            ScriptState::Normal
+        );
+        assert_eq!(
+            ScriptState::from_output(&output(Some(33), false)),
+            ScriptState::Urgent
+        );
+        assert_eq!(
+            ScriptState::from_output(&output(Some(1), false)),
+            ScriptState::Hidden
+        );
+        assert_eq!(
+            ScriptState::from_output(&output(None, true)),
+            ScriptState::Hidden
+        );
+    }
+}
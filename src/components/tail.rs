@@ -0,0 +1,389 @@
+use std::cell::Cell;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::tooltip::Hover;
+use super::waybar::WaybarFormat;
+use super::{Component, Event, Painter, Snapshot};
+use crate::command;
+use crate::error::MyBarError;
+use crate::message::Message;
+use crate::waybar::WaybarOutput;
+use xcb::x;
+
+// 进程退出后重启的等待时间，每次连续失败翻倍
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// 运行超过这么久才退出的进程不算连续失败，等待时间重新开始计算
+const STABLE_RUN: Duration = Duration::from_secs(10);
+
+/// 这次重启前等待的时间，last 是上一次等待的时间（第一次退出时为 None）
+pub(super) fn next_backoff(last: Option<Duration>, ran_for: Duration) -> Duration {
+    match last {
+        Some(last) if ran_for < STABLE_RUN => (last * 2).min(MAX_BACKOFF),
+        _ => MIN_BACKOFF,
+    }
+}
+
+/// 后台线程启动的进程，组件 drop 时结束它
+///
+/// 检查是否已经停止和保存新进程在同一把锁里完成，drop 之后才启动的进程也会被结束。
+#[derive(Default)]
+pub(super) struct Running {
+    child: Option<Child>,
+    stopped: bool,
+}
+
+impl Running {
+    /// 保存新启动的进程，组件已经 drop 时结束它并返回 false
+    pub(super) fn adopt(running: &Mutex<Running>, mut process: Child) -> bool {
+        match running.lock() {
+            Ok(mut running) if !running.stopped => {
+                running.child = Some(process);
+                true
+            }
+            _ => {
+                command::terminate(&mut process);
+                false
+            }
+        }
+    }
+
+    /// 进程的输出结束后回收它
+    pub(super) fn reap(running: &Mutex<Running>) {
+        let process = running.lock().ok().and_then(|mut r| r.child.take());
+        if let Some(mut process) = process {
+            command::terminate(&mut process);
+        }
+    }
+
+    /// 组件 drop 时调用，结束当前进程并拒绝之后启动的进程
+    pub(super) fn stop(running: &Mutex<Running>) {
+        let process = running.lock().ok().and_then(|mut r| {
+            r.stopped = true;
+            r.child.take()
+        });
+        if let Some(mut process) = process {
+            command::terminate(&mut process);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    output: WaybarOutput,
+    dirty: bool,
+}
+
+/// 运行一个长期存在的进程（例如 `xtitle -s`），每输出一行就显示这一行
+///
+/// 开启 JSON 模式后每一行是一个 Waybar 格式的对象。
+/// 进程退出后按退避时间重启；组件被 drop 时结束进程。
+pub struct Tail<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    command: String,
+    name: String,
+    json: bool,
+    format: WaybarFormat,
+    hover: Option<Hover<'a>>,
+    hovered: Cell<bool>,
+    shared: Arc<Mutex<Shared>>,
+    running: Arc<Mutex<Running>>,
+    // drop 时断开，通知后台线程不再等待重启
+    stop: Option<Sender<()>>,
+    drawn_width: Cell<f64>,
+}
+
+impl<'a> Tail<'a> {
+    pub fn new(painter: &'a Painter, command: &str) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: 200,
+            height: 40,
+            painter,
+            command: command.to_string(),
+            name: command::program_name(command),
+            json: false,
+            format: WaybarFormat::new(),
+            hover: None,
+            hovered: Cell::new(false),
+            shared: Arc::new(Mutex::new(Shared::default())),
+            running: Arc::new(Mutex::new(Running::default())),
+            stop: None,
+            drawn_width: Cell::new(0.0),
+        }
+    }
+
+    /// IPC 命令里用的名字，默认是命令的程序名
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_position(mut self, x: i16, width: u16) -> Self {
+        self.x = x;
+        self.width = width;
+        self
+    }
+
+    /// 把每一行当作 Waybar 的 JSON 对象解析，按 format 绘制
+    pub fn with_json(mut self, format: WaybarFormat) -> Self {
+        self.json = true;
+        self.format = format;
+        self
+    }
+
+    /// 鼠标悬停时弹出输出里的 tooltip
+    pub fn with_tooltips(
+        mut self,
+        conn: &'a xcb::Connection,
+        bar: x::Window,
+        visual: x::Visualtype,
+    ) -> Self {
+        self.hover = Some(Hover::new(conn, bar, visual));
+        self
+    }
+
+    pub fn start(mut self, conn: &Arc<xcb::Connection>, window: x::Window) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let conn = Arc::clone(conn);
+        let shared = Arc::clone(&self.shared);
+        let running = Arc::clone(&self.running);
+        let command = self.command.clone();
+        let json = self.json;
+        std::thread::spawn(move || {
+            let mut backoff = None;
+            loop {
+                let started = Instant::now();
+                if let Err(e) = stream(&command, json, &running, &shared, &conn, window) {
+                    eprintln!("tail {command}: {e}");
+                }
+                if stopped.try_recv() == Err(TryRecvError::Disconnected) {
+                    break;
+                }
+                let wait = next_backoff(backoff, started.elapsed());
+                backoff = Some(wait);
+                eprintln!("tail {command}: exited, restarting in {wait:?}");
+                match stopped.recv_timeout(wait) {
+                    Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+        self.stop = Some(stop);
+        self
+    }
+
+    // 鼠标在已绘制的部分上时显示当前输出的 tooltip，否则关闭
+    fn update_tooltip(&self) -> Result<(), MyBarError> {
+        let Some(hover) = &self.hover else {
+            return Ok(());
+        };
+        let tooltip = match self.shared.lock() {
+            Ok(shared) if self.hovered.get() && self.drawn_width.get() > 0.0 => {
+                shared.output.tooltip.clone()
+            }
+            _ => None,
+        };
+        hover.show(self.painter, self.x, tooltip.as_deref())
+    }
+}
+
+// 运行一次命令，直到它的 stdout 关闭
+fn stream(
+    command: &str,
+    json: bool,
+    running: &Mutex<Running>,
+    shared: &Mutex<Shared>,
+    conn: &xcb::Connection,
+    window: x::Window,
+) -> Result<(), MyBarError> {
+    let mut process = command::spawn_streaming(command, Stdio::null())?;
+    let stdout = process
+        .stdout
+        .take()
+        .ok_or_else(|| MyBarError::Other("no stdout pipe".to_string()))?;
+    if !Running::adopt(running, process) {
+        return Ok(());
+    }
+    let result = read(BufReader::new(stdout), command, json, shared, conn, window);
+    // 输出结束或者出错都要回收进程
+    Running::reap(running);
+    result
+}
+
+fn read(
+    mut stdout: impl BufRead,
+    command: &str,
+    json: bool,
+    shared: &Mutex<Shared>,
+    conn: &xcb::Connection,
+    window: x::Window,
+) -> Result<(), MyBarError> {
+    while let Some(line) = next_line(&mut stdout)? {
+        let output = if json {
+            WaybarOutput::parse(&line).unwrap_or_else(|e| {
+                eprintln!("tail {command}: invalid json output: {e}");
+                WaybarOutput::plain(&line)
+            })
+        } else {
+            WaybarOutput::plain(&line)
+        };
+        if let Ok(mut shared) = shared.lock() {
+            shared.output = output;
+            shared.dirty = true;
+        }
+        Message::TailUpdate.send(conn, window)?;
+    }
+    Ok(())
+}
+
+/// 读一行并去掉换行符，不是 UTF-8 的字节替换成 U+FFFD 而不是结束读取
+fn next_line(reader: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut buf = vec![];
+    if reader.read_until(b'\n', &mut buf)? == 0 {
+        return Ok(None);
+    }
+    if buf.ends_with(b"\n") {
+        buf.pop();
+        if buf.ends_with(b"\r") {
+            buf.pop();
+        }
+    }
+    Ok(Some(String::from_utf8_lossy(&buf).into_owned()))
+}
+
+impl Drop for Tail<'_> {
+    fn drop(&mut self) {
+        self.stop.take();
+        Running::stop(&self.running);
+    }
+}
+
+impl Component for Tail<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn close_popups(&self) {
+        self.hovered.set(false);
+        if let Some(hover) = &self.hover {
+            hover.close();
+        }
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let output = match self.shared.lock() {
+            Ok(mut shared) => {
+                shared.dirty = false;
+                shared.output.clone()
+            }
+            Err(_) => return Ok(()),
+        };
+        self.format.draw(
+            self.painter,
+            self.x as f64,
+            self.width as f64,
+            &output,
+            &self.drawn_width,
+        )
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
+        match event {
+            Event::MouseMove { x, y } => {
+                let inside =
+                    self.contains_point(*x, *y) && ((*x - self.x) as f64) < self.drawn_width.get();
+                self.hovered.set(inside);
+                self.update_tooltip()?;
+            }
+            Event::MouseLeave => {
+                self.hovered.set(false);
+                self.update_tooltip()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        match self.shared.lock() {
+            Ok(shared) => shared.output.clone().into(),
+            Err(_) => Snapshot::default(),
+        }
+    }
+
+    // 和 Script 一样，所有实例共用一个消息
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let Some(hover) = &self.hover
+            && hover.handle_x_event(event)?
+        {
+            return Ok(true);
+        }
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event {
+            let dirty = self.shared.lock().map(|s| s.dirty).unwrap_or(false);
+            if dirty && Message::from(ev.data()) == Message::TailUpdate {
+                self.draw()?;
+                self.update_tooltip()?;
+                self.painter.flush()?;
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{MAX_BACKOFF, MIN_BACKOFF, next_backoff, next_line};
+
+    #[test]
+    fn backoff_doubles_until_stable() {
+        let quick = Duration::from_millis(100);
+        assert_eq!(next_backoff(None, quick), MIN_BACKOFF);
+        assert_eq!(
+            next_backoff(Some(MIN_BACKOFF), quick),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            next_backoff(Some(Duration::from_secs(40)), quick),
+            MAX_BACKOFF
+        );
+        assert_eq!(
+            next_backoff(Some(MAX_BACKOFF), Duration::from_secs(30)),
+            MIN_BACKOFF
+        );
+    }
+
+    #[test]
+    fn lines_are_decoded_lossily() {
+        let mut input: &[u8] = b"caf\xe9\r\nok\nlast";
+        assert_eq!(
+            next_line(&mut input).unwrap().as_deref(),
+            Some("caf\u{fffd}")
+        );
+        assert_eq!(next_line(&mut input).unwrap().as_deref(), Some("ok"));
+        assert_eq!(next_line(&mut input).unwrap().as_deref(), Some("last"));
+        assert_eq!(next_line(&mut input).unwrap(), None);
+    }
+}
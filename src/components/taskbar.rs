@@ -0,0 +1,393 @@
+use std::cell::{Cell, RefCell};
+
+use xcb::{Xid, x};
+use xcb_wm::ewmh;
+
+use super::icons::IconCache;
+use super::{Component, Event, Painter, Snapshot};
+use crate::error::{MyBarError, MyResult};
+use crate::util;
+use crate::x11::WindowTracker;
+use serde_json::json;
+
+// 背景左右的留白、每一项之间的间距和图标到文字的距离
+const PADDING: f64 = 10.0;
+const ITEM_GAP: f64 = 6.0;
+const TEXT_PADDING: f64 = 6.0;
+// 窗口很少时每一项的最大宽度
+const MAX_ITEM_WIDTH: f64 = 160.0;
+// _NET_WM_DESKTOP 为这个值时窗口显示在所有桌面上
+const ALL_DESKTOPS: u32 = 0xFFFFFFFF;
+// ICCCM WM_HINTS 中的 UrgencyHint 标志
+const URGENCY_HINT: u32 = 1 << 8;
+// EWMH 的来源标识：2 表示来自分页器/任务栏
+const SOURCE_PAGER: u32 = 2;
+
+struct TaskWindow {
+    window: x::Window,
+    title: String,
+    urgent: bool,
+}
+
+/// 任务栏：列出 _NET_CLIENT_LIST 中当前桌面（或所有桌面）上的窗口
+///
+/// 左键激活窗口，中键关闭窗口。
+pub struct Taskbar<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    tracker: &'a WindowTracker<'a>,
+    icons: IconCache<'a>,
+    all_desktops: bool,
+    windows: RefCell<Vec<TaskWindow>>,
+    // 窗口列表只在相关的属性变化时重新读取，第一次绘制前还没有读过
+    loaded: Cell<bool>,
+    // 每个窗口在状态栏上占据的横向范围，用于点击定位
+    layout: RefCell<Vec<(f64, f64, x::Window)>>,
+}
+
+impl<'a> Taskbar<'a> {
+    pub fn new(painter: &'a Painter, tracker: &'a WindowTracker<'a>) -> Self {
+        Self {
+            x: 280,
+            y: 0,
+            width: 200,
+            height: 40,
+            painter,
+            tracker,
+            icons: IconCache::new(tracker, painter.icon_size()),
+            all_desktops: false,
+            windows: RefCell::new(vec![]),
+            loaded: Cell::new(false),
+            layout: RefCell::new(vec![]),
+        }
+    }
+
+    /// 显示所有桌面上的窗口，而不只是当前桌面
+    pub fn with_all_desktops(mut self, all_desktops: bool) -> Self {
+        self.all_desktops = all_desktops;
+        self
+    }
+
+    fn ewmh(&self) -> &'a ewmh::Connection<'a> {
+        self.tracker.ewmh()
+    }
+
+    fn cardinal(&self, window: x::Window, property: x::Atom) -> MyResult<Option<u32>> {
+        let reply = self.tracker.get_property(window, property, 32)?;
+        Ok(reply.and_then(|r| r.value::<u32>().first().copied()))
+    }
+
+    // _NET_WM_STATE_DEMANDS_ATTENTION 或 ICCCM 的 UrgencyHint
+    fn is_urgent(&self, window: x::Window) -> MyResult<bool> {
+        let demands_attention = self.ewmh().atoms._NET_WM_STATE_DEMANDS_ATTENTION;
+        let state = self
+            .tracker
+            .get_property(window, self.ewmh().atoms._NET_WM_STATE, 32)?;
+        if state.is_some_and(|r| r.value::<x::Atom>().contains(&demands_attention)) {
+            return Ok(true);
+        }
+        let hints = self.cardinal(window, x::ATOM_WM_HINTS)?;
+        Ok(hints.is_some_and(|flags| flags & URGENCY_HINT != 0))
+    }
+
+    fn load(&self, window: x::Window, current: Option<u32>) -> MyResult<Option<TaskWindow>> {
+        if !self.all_desktops {
+            let desktop = self.cardinal(window, self.ewmh().atoms._NET_WM_DESKTOP)?;
+            if !on_desktop(desktop, current) {
+                return Ok(None);
+            }
+        }
+        Ok(Some(TaskWindow {
+            window,
+            title: self.tracker.window_name(window)?.unwrap_or_default(),
+            urgent: self.is_urgent(window)?,
+        }))
+    }
+
+    /// 重新读取窗口列表，并订阅这些窗口的属性变化
+    fn refresh(&self) -> MyResult<()> {
+        let root = self.tracker.root();
+        let clients: Vec<x::Window> = self
+            .tracker
+            .get_property(root, self.ewmh().atoms._NET_CLIENT_LIST, 32)?
+            .map(|r| r.value::<x::Window>().to_vec())
+            .unwrap_or_default();
+        self.tracker.watch(&clients);
+        let current = self.cardinal(root, self.ewmh().atoms._NET_CURRENT_DESKTOP)?;
+
+        let mut windows = vec![];
+        for window in clients {
+            // 窗口可能在读取过程中被关闭，跳过它即可
+            match self.load(window, current) {
+                Ok(Some(task)) => windows.push(task),
+                Ok(None) => {}
+                Err(e) => eprintln!("taskbar: read {window:?}: {e}"),
+            }
+        }
+        *self.windows.borrow_mut() = windows;
+        self.loaded.set(true);
+        Ok(())
+    }
+
+    /// 只重新读取一个窗口的标题和紧急状态
+    fn reload(&self, window: x::Window) -> MyResult<()> {
+        let title = self.tracker.window_name(window)?.unwrap_or_default();
+        let urgent = self.is_urgent(window)?;
+        let mut windows = self.windows.borrow_mut();
+        if let Some(task) = windows.iter_mut().find(|task| task.window == window) {
+            task.title = title;
+            task.urgent = urgent;
+        }
+        Ok(())
+    }
+
+    fn window_at(&self, x: i16) -> Option<x::Window> {
+        let x = x as f64;
+        self.layout
+            .borrow()
+            .iter()
+            .find(|(start, width, _)| x >= *start && x < start + width)
+            .map(|(_, _, window)| *window)
+    }
+
+    fn redraw(&self) -> Result<(), MyBarError> {
+        self.draw()?;
+        self.painter.flush()
+    }
+}
+
+/// 放得下几项以及每一项的宽度；min 是一项最少需要的宽度（只有图标），
+/// 放不下所有窗口时在末尾留出 more 宽度显示 "+N"
+fn item_layout(available: f64, count: usize, min: f64, more: f64) -> (usize, f64) {
+    let fits = |n: usize, reserved: f64| {
+        (available - reserved - ITEM_GAP * n.saturating_sub(1) as f64) / n as f64
+    };
+    if count == 0 {
+        return (0, 0.0);
+    }
+    let width = fits(count, 0.0);
+    if width >= min {
+        return (count, width.min(MAX_ITEM_WIDTH));
+    }
+    // "+N" 和前一项之间也隔一个 ITEM_GAP
+    let reserved = more + ITEM_GAP;
+    match (1..count).rev().find(|&n| fits(n, reserved) >= min) {
+        Some(shown) => (shown, fits(shown, reserved).min(MAX_ITEM_WIDTH)),
+        None => (0, 0.0),
+    }
+}
+
+/// 窗口是否应该显示在当前桌面上；没有 _NET_WM_DESKTOP 的窗口总是显示
+fn on_desktop(desktop: Option<u32>, current: Option<u32>) -> bool {
+    match (desktop, current) {
+        (Some(ALL_DESKTOPS), _) | (None, _) | (_, None) => true,
+        (Some(desktop), Some(current)) => desktop == current,
+    }
+}
+
+impl Component for Taskbar<'_> {
+    fn name(&self) -> &str {
+        "taskbar"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        if !self.loaded.get() {
+            self.refresh()?;
+        }
+        let x = self.x as f64;
+        let width = self.width as f64;
+        self.painter.clear_area(x, width)?;
+        self.layout.borrow_mut().clear();
+
+        let windows = self.windows.borrow();
+        if windows.is_empty() {
+            return Ok(());
+        }
+        self.painter
+            .draw_rounded_background(x, width, 10.0, "#475164")?;
+
+        // 窗口太多时每一项至少能放下图标，剩下的折叠成 "+N"
+        let min = self.icons.size() + TEXT_PADDING * 2.0;
+        let more = self.painter.text_width(&format!("+{}", windows.len()))?;
+        let (shown, item_width) = item_layout(width - PADDING * 2.0, windows.len(), min, more);
+        let text_width = item_width - self.icons.size() - TEXT_PADDING * 2.0;
+        let active = self.tracker.active();
+        let mut item_x = x + PADDING;
+        for task in windows.iter().take(shown) {
+            let (background, color) = if task.urgent {
+                (Some("#8b2d2d"), "#ff0000")
+            } else if Some(task.window) == active {
+                (Some("#5c6b86"), "#ff3399")
+            } else {
+                (None, "#ffffff")
+            };
+            if let Some(background) = background {
+                self.painter
+                    .draw_rounded_background(item_x, item_width, 6.0, background)?;
+            }
+            self.icons
+                .draw(self.painter, task.window, item_x + TEXT_PADDING, color)?;
+            if text_width > 0.0 {
+                let title = util::ellipsize(&task.title, text_width, false, |s| {
+                    self.painter.text_width(s).unwrap_or(f64::MAX)
+                });
+                let text_x = item_x + TEXT_PADDING + self.icons.size() + TEXT_PADDING;
+                self.painter.draw_text(text_x, 10.0, &title, color)?;
+            }
+            self.layout
+                .borrow_mut()
+                .push((item_x, item_width, task.window));
+            item_x += item_width + ITEM_GAP;
+        }
+        if shown < windows.len() {
+            let text = format!("+{}", windows.len() - shown);
+            self.painter.draw_text(item_x, 10.0, &text, "#ffffff")?;
+        }
+        Ok(())
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
+        let Event::MouseClick { x, y, button, .. } = event else {
+            return Ok(());
+        };
+        if !self.contains_point(*x, *y) {
+            return Ok(());
+        }
+        let Some(window) = self.window_at(*x) else {
+            return Ok(());
+        };
+        match *button {
+            // 左键激活窗口
+            1 => self
+                .ewmh()
+                .send_and_check_request(&ewmh::proto::SendActiveWindow::new(
+                    self.ewmh(),
+                    window,
+                    SOURCE_PAGER,
+                    x::CURRENT_TIME,
+                    None,
+                ))?,
+            // 中键关闭窗口
+            2 => self
+                .ewmh()
+                .send_and_check_request(&ewmh::proto::SendCloseWindow::new(
+                    self.ewmh(),
+                    window,
+                    SOURCE_PAGER,
+                    x::CURRENT_TIME,
+                ))?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let windows = self.windows.borrow();
+        let titles: Vec<&str> = windows.iter().map(|w| w.title.as_str()).collect();
+        let state: Vec<_> = windows
+            .iter()
+            .map(|w| {
+                json!({
+                    "window": w.window.resource_id(),
+                    "title": w.title,
+                    "urgent": w.urgent,
+                })
+            })
+            .collect();
+        Snapshot::new(titles.join(" | "), json!({ "windows": state }))
+    }
+
+    // IPC 的 update 命令：重新读取窗口列表
+    fn update(&self) -> Result<(), MyBarError> {
+        self.refresh()?;
+        self.redraw()
+    }
+
+    // 其它组件也关心这些事件，所以总是返回 false
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        let atoms = &self.ewmh().atoms;
+        match event {
+            xcb::Event::X(x::Event::PropertyNotify(ev)) if ev.window() == self.tracker.root() => {
+                let atom = ev.atom();
+                if atom == atoms._NET_CLIENT_LIST || atom == atoms._NET_CURRENT_DESKTOP {
+                    self.refresh()?;
+                    self.redraw()?;
+                } else if atom == atoms._NET_ACTIVE_WINDOW {
+                    // 活动窗口只影响高亮，不需要重新读取
+                    self.redraw()?;
+                }
+            }
+            xcb::Event::X(x::Event::PropertyNotify(ev)) => {
+                let atom = ev.atom();
+                let window = ev.window();
+                let listed = self
+                    .windows
+                    .borrow()
+                    .iter()
+                    .any(|task| task.window == window);
+                if atom == atoms._NET_WM_DESKTOP {
+                    // 窗口换到当前桌面时它原本不在列表里
+                    self.refresh()?;
+                    self.redraw()?;
+                } else if listed && atom == atoms._NET_WM_ICON {
+                    self.icons.forget(window);
+                    self.redraw()?;
+                } else if listed
+                    && [
+                        atoms._NET_WM_NAME,
+                        x::ATOM_WM_NAME,
+                        atoms._NET_WM_STATE,
+                        x::ATOM_WM_HINTS,
+                    ]
+                    .contains(&atom)
+                {
+                    self.reload(window)?;
+                    self.redraw()?;
+                }
+            }
+            xcb::Event::X(x::Event::DestroyNotify(ev)) => {
+                self.icons.forget(ev.window());
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ALL_DESKTOPS, item_layout, on_desktop};
+
+    #[test]
+    fn desktop_filter() {
+        assert!(on_desktop(Some(1), Some(1)));
+        assert!(!on_desktop(Some(0), Some(1)));
+        assert!(on_desktop(Some(ALL_DESKTOPS), Some(1)));
+        assert!(on_desktop(None, Some(1)));
+    }
+
+    #[test]
+    fn collapse_items_that_do_not_fit() {
+        // 两项各 97，不超过最大宽度
+        assert_eq!(item_layout(200.0, 2, 32.0, 20.0), (2, 97.0));
+        assert_eq!(item_layout(1000.0, 2, 32.0, 20.0).1, 160.0);
+        // 5 项放不下 (5 * 32 + 4 * 6 > 180)，留出 "+N" 后放 4 项
+        assert_eq!(item_layout(180.0, 5, 32.0, 20.0), (4, 34.0));
+        assert_eq!(item_layout(40.0, 5, 32.0, 20.0), (0, 0.0));
+        assert_eq!(item_layout(200.0, 0, 32.0, 20.0), (0, 0.0));
+    }
+}
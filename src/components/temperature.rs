@@ -0,0 +1,233 @@
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use super::{Component, Event, Painter, Snapshot};
+use crate::error::MyBarError;
+use crate::message::Message;
+use crate::scheduler::Scheduler;
+use crate::temperature::{Fan, Sensor, SensorSource};
+use serde_json::json;
+use xcb::x;
+
+// 按温度从低到高的温度计图标
+const RAMP: [&str; 5] = ["\u{f2cb}", "\u{f2ca}", "\u{f2c9}", "\u{f2c8}", "\u{f2c7}"];
+const FAN_ICON: &str = "\u{f0210}";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+// 图标按这个温度区间分档
+const RAMP_MIN: f64 = 30.0;
+const RAMP_MAX: f64 = 90.0;
+// 传感器没有报告临界温度时使用
+const DEFAULT_CRITICAL: f64 = 90.0;
+
+pub struct Temperature<'a> {
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    source: SensorSource,
+    // 传感器标签或芯片名，例如 "Package id 0"、"Tctl"
+    label: Option<String>,
+    warning: f64,
+    // 为 None 时使用传感器报告的临界温度
+    critical: Option<f64>,
+    show_fan: bool,
+    sensor: RefCell<Option<Sensor>>,
+    fans: RefCell<Vec<Fan>>,
+    ticker: Option<timer::Guard>,
+    drawn_width: Cell<f64>,
+}
+
+impl<'a> Temperature<'a> {
+    pub fn new(painter: &'a Painter, source: SensorSource) -> Self {
+        Self {
+            x: 1280,
+            y: 0,
+            width: 70,
+            height: 40,
+            painter,
+            source,
+            label: None,
+            warning: 70.0,
+            critical: None,
+            show_fan: false,
+            sensor: RefCell::new(None),
+            fans: RefCell::new(vec![]),
+            ticker: None,
+            drawn_width: Cell::new(0.0),
+        }
+    }
+
+    pub fn with_sensor(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// 超过 warning 显示警告色，超过 critical 显示红色背景，
+    /// 不指定时 critical 使用传感器的 temp*_crit
+    pub fn with_thresholds(mut self, warning: f64, critical: f64) -> Self {
+        self.warning = warning;
+        self.critical = Some(critical);
+        self
+    }
+
+    pub fn with_fan(mut self, show_fan: bool) -> Self {
+        self.show_fan = show_fan;
+        self
+    }
+
+    pub fn with_refresh(mut self, scheduler: &Scheduler) -> Self {
+        self.ticker = Some(scheduler.every(REFRESH_INTERVAL, Message::TemperatureUpdate));
+        self
+    }
+
+    fn refresh(&self) {
+        match self.source.sensor(self.label.as_deref()) {
+            Ok(sensor) => *self.sensor.borrow_mut() = sensor,
+            Err(e) => eprintln!("read temperature: {e}"),
+        }
+        if self.show_fan {
+            *self.fans.borrow_mut() = self.source.fans();
+        }
+    }
+
+    // 文字颜色和背景色
+    fn style(&self, sensor: &Sensor) -> (&'static str, &'static str) {
+        let celsius = sensor.celsius;
+        let critical = self
+            .critical
+            .or(sensor.critical)
+            .unwrap_or(DEFAULT_CRITICAL);
+        if celsius >= critical {
+            ("#ffffff", "#8b2d2d")
+        } else if celsius >= self.warning {
+            ("#ff9900", "#475164")
+        } else {
+            ("#ff3329", "#475164")
+        }
+    }
+
+    fn text(&self, sensor: &Sensor) -> String {
+        let celsius = format!("{:.0}°C", sensor.celsius);
+        match self.fan() {
+            Some(fan) => format!("{celsius} {fan}"),
+            None => celsius,
+        }
+    }
+
+    // 只显示第一个在转的风扇
+    fn fan(&self) -> Option<String> {
+        let fans = self.fans.borrow();
+        let fan = fans.iter().find(|fan| fan.rpm > 0)?;
+        Some(format!("{FAN_ICON} {}", fan.rpm))
+    }
+}
+
+fn icon(celsius: f64) -> &'static str {
+    let ratio = ((celsius - RAMP_MIN) / (RAMP_MAX - RAMP_MIN)).clamp(0.0, 1.0);
+    RAMP[(ratio * (RAMP.len() - 1) as f64).round() as usize]
+}
+
+impl Component for Temperature<'_> {
+    fn name(&self) -> &str {
+        "temperature"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        self.refresh();
+        let sensor = self.sensor.borrow();
+        let Some(sensor) = sensor.as_ref() else {
+            self.painter
+                .clear_area(self.x as f64, self.drawn_width.get())?;
+            self.drawn_width.set(0.0);
+            return Ok(());
+        };
+        let (color, background) = self.style(sensor);
+        let icon = icon(sensor.celsius);
+        let iw = self.painter.text_width(icon)?;
+        let max = self.width as f64 - iw - 5.0 - 10.0 * 2.0;
+        // 风扇转速放不下时不画，只在 snapshot 里输出
+        let mut text = self.text(sensor);
+        if self.painter.text_width(&text)? > max {
+            text = format!("{:.0}°C", sensor.celsius);
+        }
+        let tw = self.painter.text_width(&text)?.min(max);
+        let w = iw + 5.0 + tw + 10.0 * 2.0;
+
+        if self.drawn_width.get() > w {
+            self.painter
+                .clear_area(self.x as f64, self.drawn_width.get())?;
+        }
+        self.drawn_width.set(w);
+        self.painter
+            .draw_rounded_background(self.x as f64, w, 10.0, background)?;
+        let cr = &self.painter.cairo_conn;
+        cr.save()?;
+        cr.rectangle(self.x as f64, 0.0, w, self.painter.height() as f64);
+        cr.clip();
+        let result = self
+            .painter
+            .draw_text(self.x as f64 + 10.0, 10.0, icon, color)
+            .and_then(|_| {
+                self.painter
+                    .draw_text(self.x as f64 + 10.0 + iw + 5.0, 10.0, &text, color)
+            });
+        cr.restore()?;
+        result
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x
+            && x <= self.x + self.width as i16
+            && y >= self.y
+            && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, _event: &Event) -> Result<(), MyBarError> {
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x, self.y, self.width, self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let sensor = self.sensor.borrow();
+        let Some(sensor) = sensor.as_ref() else {
+            return Snapshot::default();
+        };
+        let fans: Vec<_> = self
+            .fans
+            .borrow()
+            .iter()
+            .map(|fan| json!({ "label": fan.label, "rpm": fan.rpm }))
+            .collect();
+        Snapshot::new(
+            self.text(sensor),
+            json!({ "label": sensor.label, "celsius": sensor.celsius, "fans": fans }),
+        )
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event
+            && Message::from(ev.data()) == Message::TemperatureUpdate
+        {
+            self.draw()?;
+            self.painter.flush()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::icon;
+
+    #[test]
+    fn icon_ramp() {
+        assert_eq!(icon(20.0), "\u{f2cb}");
+        assert_eq!(icon(60.0), "\u{f2c9}");
+        assert_eq!(icon(100.0), "\u{f2c7}");
+    }
+}
@@ -1,8 +1,38 @@
-use std::sync::Mutex;
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
 
-use super::{Component, Event, Painter};
-use crate::error::{MyBarError, MyResult};
-use xcb_wm::ewmh;
+use super::icons::IconCache;
+use super::rewrite::{self, TitleRule};
+use super::{Component, Event, Painter, Snapshot};
+use crate::error::MyBarError;
+use crate::message::Message;
+use crate::scheduler::Scheduler;
+use crate::util;
+use crate::x11::WindowTracker;
+use serde_json::json;
+use xcb::{Xid, x};
+
+// 跑马灯每一帧的间隔和移动的像素
+const MARQUEE_INTERVAL: Duration = Duration::from_millis(50);
+const MARQUEE_STEP: f64 = 2.0;
+// 跑马灯首尾之间的空白
+const MARQUEE_GAP: f64 = 40.0;
+// 背景左边到图标、图标到文字、文字到背景右边的距离
+const ICON_PADDING: f64 = 15.0;
+const TEXT_PADDING: f64 = 8.0;
+const RIGHT_PADDING: f64 = 30.0;
+const TEXT_COLOR: &str = "#ff3329";
+
+/// 标题超出宽度时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    /// 末尾省略："很长的标…"
+    End,
+    /// 中间省略："很长…标题"
+    Middle,
+    /// 循环滚动
+    Marquee,
+}
 
 pub struct Title<'a> {
     x: i16,
@@ -10,34 +40,192 @@ pub struct Title<'a> {
     width: u16,
     height: u16,
     painter: &'a Painter<'a>,
-    conn: &'a ewmh::Connection<'a>,
+    tracker: &'a WindowTracker<'a>,
+    // 左键在几种方式之间切换
+    overflow: Cell<Overflow>,
+    scheduler: Option<&'a Scheduler>,
+    rules: Vec<TitleRule>,
+    // 跑马灯状态：当前标题、颜色、偏移量和定时器
+    title: RefCell<String>,
+    color: RefCell<String>,
+    offset: Cell<f64>,
+    ticker: RefCell<Option<timer::Guard>>,
+    window: Cell<Option<x::Window>>,
+    // 没有聚焦窗口时显示的文字，{desktop} 会被替换成当前桌面名
+    placeholder: String,
+    icons: IconCache<'a>,
 }
 
 impl<'a> Title<'a> {
-    pub fn new(painter: &'a Painter, conn: &'a ewmh::Connection) -> Self {
+    pub fn new(painter: &'a Painter, tracker: &'a WindowTracker<'a>) -> Self {
         Self {
-            x: 300,
+            x: 280,
             y: 0,
-            width: 300,
+            width: 130,
             height: 40,
             painter,
-            conn,
+            tracker,
+            overflow: Cell::new(Overflow::End),
+            scheduler: None,
+            rules: vec![],
+            title: RefCell::new(String::new()),
+            color: RefCell::new(TEXT_COLOR.to_string()),
+            offset: Cell::new(0.0),
+            ticker: RefCell::new(None),
+            window: Cell::new(None),
+            placeholder: "{desktop}".to_string(),
+            icons: IconCache::new(tracker, painter.icon_size()),
         }
     }
-}
 
-impl Component for Title<'_> {
-    fn draw(&self) -> Result<(), MyBarError> {
-        let title = get_current_wm_title(self.conn)?;
-        let tw = self.painter.text_width(&title)?;
-        let w = tw + 30.0 * 2.0;
+    pub fn with_overflow(self, overflow: Overflow) -> Self {
+        self.overflow.set(overflow);
+        self
+    }
+
+    /// 添加一条标题改写规则，见 [`TitleRule`]
+    pub fn with_rule(mut self, rule: TitleRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn with_placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = placeholder.to_string();
+        self
+    }
+
+    fn placeholder(&self) -> String {
+        if !self.placeholder.contains("{desktop}") {
+            return self.placeholder.clone();
+        }
+        let desktop = self.tracker.desktop_name().unwrap_or_else(|e| {
+            eprintln!("get desktop name: {e}");
+            None
+        });
+        self.placeholder
+            .replace("{desktop}", desktop.as_deref().unwrap_or_default())
+    }
+
+    // 活动窗口和它的标题；窗口可能已经消失，这时当作没有活动窗口
+    fn current(&self) -> (Option<x::Window>, String) {
+        let Some(window) = self.tracker.active() else {
+            return (None, self.placeholder());
+        };
+        match self.tracker.window_name(window) {
+            Ok(name) => (Some(window), name.unwrap_or_default()),
+            Err(e) => {
+                eprintln!("get title of {window:?}: {e}");
+                (None, self.placeholder())
+            }
+        }
+    }
+
+    /// 跑马灯需要定时重绘
+    pub fn with_marquee(mut self, scheduler: &'a Scheduler) -> Self {
+        self.overflow.set(Overflow::Marquee);
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    // 末尾省略 -> 中间省略 -> 跑马灯（需要 scheduler）-> 末尾省略
+    fn cycle_overflow(&self) {
+        let next = match self.overflow.get() {
+            Overflow::End => Overflow::Middle,
+            Overflow::Middle if self.scheduler.is_some() => Overflow::Marquee,
+            Overflow::Middle | Overflow::Marquee => Overflow::End,
+        };
+        self.overflow.set(next);
+    }
+
+    // 只有标题放不下时才开定时器
+    fn update_ticker(&self, overflowing: bool) {
+        let mut ticker = self.ticker.borrow_mut();
+        match (overflowing, ticker.is_some(), self.scheduler) {
+            (true, false, Some(scheduler)) => {
+                *ticker = Some(scheduler.every(MARQUEE_INTERVAL, Message::TitleTick));
+            }
+            (false, true, _) => {
+                ticker.take();
+            }
+            _ => {}
+        }
+    }
+
+    fn draw_title(&self, title: &str, color: &str) -> Result<(), MyBarError> {
+        let max = self.width as f64;
+        let tw = self.painter.text_width(title)?;
+        let text_x = self.x as f64 + ICON_PADDING + self.icons.size() + TEXT_PADDING;
+        let left = text_x - self.x as f64;
+
+        if *self.title.borrow() != title {
+            *self.title.borrow_mut() = title.to_string();
+            self.offset.set(0.0);
+        }
+        *self.color.borrow_mut() = color.to_string();
 
         self.painter
-            .draw_rounded_background(self.x as f64, w, 10.0, "#475164")?;
-        self.painter
-            .draw_text(self.x as f64 + 30.0, 10.0, &title, "#ff3329")?;
+            .clear_area(self.x as f64, left + max + RIGHT_PADDING)?;
+        self.painter.draw_rounded_background(
+            self.x as f64,
+            left + tw.min(max) + RIGHT_PADDING,
+            10.0,
+            "#475164",
+        )?;
+        if let Some(window) = self.window.get() {
+            let x = self.x as f64 + ICON_PADDING;
+            // 字形图标和标题用同一个颜色，这样按 WM_CLASS 指定的颜色也作用在图标上
+            self.icons.draw(self.painter, window, x, color)?;
+        }
 
-        Ok(())
+        // 文字限制在组件范围内
+        let cr = &self.painter.cairo_conn;
+        cr.save()?;
+        cr.rectangle(text_x, self.y as f64, max, self.height as f64);
+        cr.clip();
+        let result = match self.overflow.get() {
+            Overflow::Marquee if tw > max => {
+                self.update_ticker(true);
+                let x = text_x - self.offset.get();
+                self.painter.draw_text(x, 10.0, title, color).and_then(|_| {
+                    self.painter
+                        .draw_text(x + tw + MARQUEE_GAP, 10.0, title, color)
+                })
+            }
+            overflow => {
+                self.update_ticker(false);
+                let text = util::ellipsize(title, max, overflow == Overflow::Middle, |s| {
+                    self.painter.text_width(s).unwrap_or(f64::MAX)
+                });
+                self.painter.draw_text(text_x, 10.0, &text, color)
+            }
+        };
+        cr.restore()?;
+        result
+    }
+
+    // 跑马灯前进一帧
+    fn tick(&self) -> Result<(), MyBarError> {
+        let title = self.title.borrow().clone();
+        let color = self.color.borrow().clone();
+        let tw = self.painter.text_width(&title)?;
+        let offset = (self.offset.get() + MARQUEE_STEP) % (tw + MARQUEE_GAP);
+        self.offset.set(offset);
+        self.draw_title(&title, &color)?;
+        self.painter.flush()
+    }
+}
+
+impl Component for Title<'_> {
+    fn name(&self) -> &str {
+        "title"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let (window, title) = self.current();
+        self.window.set(window);
+        let class = window.and_then(|w| self.icons.class(w));
+        let (title, color) = rewrite::apply(&self.rules, class.as_deref(), &title);
+        self.draw_title(&title, color.unwrap_or(TEXT_COLOR))
     }
 
     fn contains_point(&self, x: i16, y: i16) -> bool {
@@ -49,15 +237,15 @@ impl Component for Title<'_> {
 
     fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
         match event {
-            Event::MouseClick { x, y, button } => {
-                if self.contains_point(*x, *y) {
-                    println!("Title clicked at ({}, {})", x, y);
-                    // TODO: 实现标题组件点击逻辑
-                }
+            Event::MouseClick { x, y, button: 1, .. } if self.contains_point(*x, *y) => {
+                self.cycle_overflow();
+                self.draw()?;
+                self.painter.flush()?;
             }
-            Event::KeyPress { keycode } => {
+            Event::KeyPress { .. } => {
                 // TODO: 实现标题组件键盘控制逻辑
             }
+            _ => {}
         }
         Ok(())
     }
@@ -65,15 +253,23 @@ impl Component for Title<'_> {
     fn get_bounds(&self) -> (i16, i16, u16, u16) {
         (self.x, self.y, self.width, self.height)
     }
-}
 
-pub fn get_current_window(conn: &ewmh::Connection) -> MyResult<xcb::x::Window> {
-    let reply = conn.wait_for_reply(conn.send_request(&ewmh::proto::GetActiveWindow))?;
-    Ok(reply.window)
-}
+    fn snapshot(&self) -> Snapshot {
+        let window = self.window.get().map(|window| window.resource_id());
+        Snapshot::new(self.title.borrow().as_str(), json!({ "window": window }))
+    }
 
-fn get_current_wm_title(conn: &ewmh::Connection) -> MyResult<String> {
-    let window = get_current_window(conn)?;
-    let reply = conn.wait_for_reply(conn.send_request(&ewmh::proto::GetWmName(window)))?;
-    Ok(reply.name)
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        if let xcb::Event::X(x::Event::ClientMessage(ev)) = event
+            && Message::from(ev.data()) == Message::TitleTick
+        {
+            self.tick()?;
+            return Ok(true);
+        }
+        // 窗口销毁后它的图标不会再用到
+        if let xcb::Event::X(x::Event::DestroyNotify(ev)) = event {
+            self.icons.forget(ev.window());
+        }
+        Ok(false)
+    }
 }
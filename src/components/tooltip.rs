@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+
+use xcb::x;
+
+use super::Painter;
+use crate::error::{MyBarError, MyResult};
+use crate::x11::{create_popup, popup_geometry};
+
+const LINE_HEIGHT: f64 = 20.0;
+const PADDING: f64 = 10.0;
+
+/// 鼠标悬停时弹出的提示窗口，支持多行文字
+pub struct Tooltip<'a> {
+    conn: &'a xcb::Connection,
+    window: x::Window,
+    width: u16,
+    height: u16,
+    painter: Painter<'a>,
+    text: String,
+}
+
+impl<'a> Tooltip<'a> {
+    /// 用状态栏的 painter 量出文字宽度，在 anchor_x 处弹出
+    pub fn open(
+        conn: &'a xcb::Connection,
+        bar: x::Window,
+        visual: x::Visualtype,
+        measure: &Painter,
+        anchor_x: i16,
+        text: &str,
+    ) -> MyResult<Self> {
+        let mut text_width: f64 = 0.0;
+        for line in text.lines() {
+            text_width = text_width.max(measure.text_width(line)?);
+        }
+        let lines = text.lines().count().max(1);
+        let width = (text_width + PADDING * 2.0).ceil() as u16;
+        let height = (LINE_HEIGHT * lines as f64 + PADDING * 2.0) as u16;
+
+        let (root, geometry) = popup_geometry(conn, bar, anchor_x, width, height)?;
+        let window = create_popup(conn, bar, root, &visual, geometry)?;
+        conn.send_request(&x::MapWindow { window });
+        conn.flush()?;
+
+        let painter = Painter::new(conn, window, visual, width as i32, height as i32)?;
+        Ok(Self {
+            conn,
+            window,
+            width,
+            height,
+            painter,
+            text: text.to_string(),
+        })
+    }
+
+    pub fn window(&self) -> x::Window {
+        self.window
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn draw(&self) -> Result<(), MyBarError> {
+        let painter = &self.painter;
+        painter.set_hex_color("#475164")?;
+        painter
+            .cairo_conn
+            .rectangle(0.0, 0.0, self.width as f64, self.height as f64);
+        painter.cairo_conn.fill()?;
+        for (i, line) in self.text.lines().enumerate() {
+            let y = PADDING + LINE_HEIGHT * (i as f64 + 0.5);
+            painter.draw_text_at(PADDING, y, line, "#ffffff")?;
+        }
+        painter.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Tooltip<'_> {
+    fn drop(&mut self) {
+        self.painter.cairo_conn.target().finish();
+        self.conn.send_request(&x::DestroyWindow {
+            window: self.window,
+        });
+        if let Err(e) = self.conn.flush() {
+            eprintln!("close tooltip: {e}");
+        }
+    }
+}
+
+/// 组件用来管理自己的悬停提示：鼠标在组件上并且有提示文字时显示，否则关闭
+pub struct Hover<'a> {
+    conn: &'a xcb::Connection,
+    bar: x::Window,
+    visual: x::Visualtype,
+    tooltip: RefCell<Option<Tooltip<'a>>>,
+}
+
+impl<'a> Hover<'a> {
+    pub fn new(conn: &'a xcb::Connection, bar: x::Window, visual: x::Visualtype) -> Self {
+        Self {
+            conn,
+            bar,
+            visual,
+            tooltip: RefCell::new(None),
+        }
+    }
+
+    /// text 为 None 时关闭提示；文字变了就重新弹出
+    pub fn show(&self, measure: &Painter, anchor_x: i16, text: Option<&str>) -> MyResult<()> {
+        let mut tooltip = self.tooltip.borrow_mut();
+        match text {
+            Some(text) if tooltip.as_ref().is_some_and(|t| t.text() == text) => {}
+            Some(text) if !text.is_empty() => {
+                tooltip.take();
+                *tooltip = Some(Tooltip::open(
+                    self.conn,
+                    self.bar,
+                    self.visual,
+                    measure,
+                    anchor_x,
+                    text,
+                )?);
+            }
+            _ => {
+                tooltip.take();
+            }
+        }
+        Ok(())
+    }
+
+    /// 不管鼠标在哪都关闭提示
+    pub fn close(&self) {
+        self.tooltip.borrow_mut().take();
+    }
+
+    /// 处理提示窗口的 Expose，返回 true 表示是提示窗口的事件
+    pub fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        let tooltip = self.tooltip.borrow();
+        let Some(tooltip) = tooltip.as_ref() else {
+            return Ok(false);
+        };
+        match event {
+            xcb::Event::X(x::Event::Expose(ev)) if ev.window() == tooltip.window() => {
+                if ev.count() == 0 {
+                    tooltip.draw()?;
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
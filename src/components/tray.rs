@@ -0,0 +1,497 @@
+use std::cell::{Cell, RefCell};
+
+use xcb::{Xid, x};
+
+use super::{Component, Event, Painter, Snapshot};
+use crate::error::{MyBarError, MyResult};
+use serde_json::json;
+
+// 图标大小由状态栏高度减去上下留白得到
+const ICON_MARGIN: u16 = 8;
+const ICON_GAP: u16 = 6;
+const PADDING: u16 = 10;
+// 状态栏给托盘留了这么多图标的位置，多出来的图标不显示
+const MAX_ICONS: u16 = 3;
+// 系统托盘协议的操作码
+const SYSTEM_TRAY_REQUEST_DOCK: u32 = 0;
+// XEMBED 消息和 _XEMBED_INFO 标志
+const XEMBED_EMBEDDED_NOTIFY: u32 = 0;
+const XEMBED_VERSION: u32 = 0;
+const XEMBED_MAPPED: u32 = 1 << 0;
+
+struct TrayAtoms {
+    selection: x::Atom,
+    opcode: x::Atom,
+    manager: x::Atom,
+    xembed: x::Atom,
+    xembed_info: x::Atom,
+    visual: x::Atom,
+    orientation: x::Atom,
+}
+
+struct TrayIcon {
+    window: x::Window,
+    mapped: bool,
+}
+
+/// 系统托盘：按 freedesktop System Tray 协议获取 _NET_SYSTEM_TRAY_S{screen} 选区，
+/// 把请求停靠的图标以 XEMBED 客户端的形式嵌入状态栏
+///
+/// 托盘的右边缘固定，图标增多时向左扩展，最多扩展到 MAX_ICONS 个图标。状态栏使用 32 位视觉，
+/// 这个视觉通过 _NET_SYSTEM_TRAY_VISUAL 告诉客户端，支持的客户端会创建带透明通道的图标窗口。
+pub struct Tray<'a> {
+    right: i16,
+    y: i16,
+    height: u16,
+    painter: &'a Painter<'a>,
+    conn: &'a xcb::Connection,
+    bar: x::Window,
+    root: x::Window,
+    // 持有选区、接收停靠请求的窗口
+    owner: x::Window,
+    atoms: TrayAtoms,
+    icons: RefCell<Vec<TrayIcon>>,
+    // 上一次绘制的宽度，图标减少时需要擦掉
+    drawn_width: Cell<u16>,
+}
+
+fn intern(conn: &xcb::Connection, name: &str) -> MyResult<x::Atom> {
+    let reply = conn.wait_for_reply(conn.send_request(&x::InternAtom {
+        only_if_exists: false,
+        name: name.as_bytes(),
+    }))?;
+    Ok(reply.atom())
+}
+
+impl<'a> Tray<'a> {
+    /// 获取托盘选区，已经有别的托盘在运行时返回错误
+    pub fn new(
+        painter: &'a Painter<'a>,
+        conn: &'a xcb::Connection,
+        bar: x::Window,
+        screen_num: i32,
+        visual: &x::Visualtype,
+    ) -> MyResult<Self> {
+        let atoms = TrayAtoms {
+            selection: intern(conn, &format!("_NET_SYSTEM_TRAY_S{screen_num}"))?,
+            opcode: intern(conn, "_NET_SYSTEM_TRAY_OPCODE")?,
+            manager: intern(conn, "MANAGER")?,
+            xembed: intern(conn, "_XEMBED")?,
+            xembed_info: intern(conn, "_XEMBED_INFO")?,
+            visual: intern(conn, "_NET_SYSTEM_TRAY_VISUAL")?,
+            orientation: intern(conn, "_NET_SYSTEM_TRAY_ORIENTATION")?,
+        };
+        let current = conn.wait_for_reply(conn.send_request(&x::GetSelectionOwner {
+            selection: atoms.selection,
+        }))?;
+        if !current.owner().is_none() {
+            return Err(MyBarError::Other(
+                "another system tray is already running".to_string(),
+            ));
+        }
+
+        let root = conn
+            .wait_for_reply(conn.send_request(&x::GetGeometry {
+                drawable: x::Drawable::Window(bar),
+            }))?
+            .root();
+        let owner = conn.generate_id();
+        conn.check_request(conn.send_request_checked(&x::CreateWindow {
+            depth: x::COPY_FROM_PARENT as u8,
+            wid: owner,
+            parent: root,
+            x: -1,
+            y: -1,
+            width: 1,
+            height: 1,
+            border_width: 0,
+            class: x::WindowClass::InputOnly,
+            visual: x::COPY_FROM_PARENT,
+            value_list: &[x::Cw::OverrideRedirect(true)],
+        }))?;
+        // 水平方向，图标使用和状态栏相同的 32 位视觉
+        conn.send_request(&x::ChangeProperty {
+            mode: x::PropMode::Replace,
+            window: owner,
+            property: atoms.orientation,
+            r#type: x::ATOM_CARDINAL,
+            data: &[0u32],
+        });
+        conn.send_request(&x::ChangeProperty {
+            mode: x::PropMode::Replace,
+            window: owner,
+            property: atoms.visual,
+            r#type: x::ATOM_VISUALID,
+            data: &[visual.visual_id()],
+        });
+
+        conn.send_request(&x::SetSelectionOwner {
+            owner,
+            selection: atoms.selection,
+            time: x::CURRENT_TIME,
+        });
+        let current = conn.wait_for_reply(conn.send_request(&x::GetSelectionOwner {
+            selection: atoms.selection,
+        }))?;
+        if current.owner() != owner {
+            conn.send_request(&x::DestroyWindow { window: owner });
+            return Err(MyBarError::Other(
+                "failed to acquire the system tray selection".to_string(),
+            ));
+        }
+
+        // 通知已经在等待托盘的客户端
+        let manager = x::ClientMessageEvent::new(
+            root,
+            atoms.manager,
+            x::ClientMessageData::Data32([
+                x::CURRENT_TIME,
+                atoms.selection.resource_id(),
+                owner.resource_id(),
+                0,
+                0,
+            ]),
+        );
+        conn.check_request(conn.send_request_checked(&x::SendEvent {
+            propagate: false,
+            destination: x::SendEventDest::Window(root),
+            event_mask: x::EventMask::STRUCTURE_NOTIFY,
+            event: &manager,
+        }))?;
+
+        Ok(Self {
+            right: 1910,
+            y: 0,
+            height: 40,
+            painter,
+            conn,
+            bar,
+            root,
+            owner,
+            atoms,
+            icons: RefCell::new(vec![]),
+            drawn_width: Cell::new(0),
+        })
+    }
+
+    fn icon_size(&self) -> u16 {
+        self.height - ICON_MARGIN * 2
+    }
+
+    fn width(&self) -> u16 {
+        let count = self.icons.borrow().iter().filter(|i| i.mapped).count() as u16;
+        tray_width(count.min(MAX_ICONS), self.icon_size())
+    }
+
+    fn x(&self) -> i16 {
+        self.right - self.width() as i16
+    }
+
+    fn is_icon(&self, window: x::Window) -> bool {
+        self.icons.borrow().iter().any(|i| i.window == window)
+    }
+
+    // 没有 _XEMBED_INFO 的客户端按照惯例直接映射
+    fn wants_mapped(&self, window: x::Window) -> MyResult<bool> {
+        let reply = self
+            .conn
+            .wait_for_reply(self.conn.send_request(&x::GetProperty {
+                delete: false,
+                window,
+                property: self.atoms.xembed_info,
+                r#type: x::ATOM_ANY,
+                long_offset: 0,
+                long_length: 2,
+            }))?;
+        let info = (reply.format() == 32).then(|| reply.value::<u32>());
+        Ok(mapped_from_info(info))
+    }
+
+    fn dock(&self, window: x::Window) -> MyResult<()> {
+        if self.is_icon(window) {
+            return Ok(());
+        }
+        let conn = self.conn;
+        conn.check_request(conn.send_request_checked(&x::ChangeWindowAttributes {
+            window,
+            value_list: &[x::Cw::EventMask(
+                x::EventMask::STRUCTURE_NOTIFY | x::EventMask::PROPERTY_CHANGE,
+            )],
+        }))?;
+        // 状态栏意外退出时图标会回到根窗口，而不是跟着被销毁
+        conn.send_request(&x::ChangeSaveSet {
+            mode: x::SetMode::Insert,
+            window,
+        });
+        let size = self.icon_size();
+        conn.send_request(&x::ReparentWindow {
+            window,
+            parent: self.bar,
+            x: self.x(),
+            y: self.y + ICON_MARGIN as i16,
+        });
+        conn.send_request(&x::ConfigureWindow {
+            window,
+            value_list: &[
+                x::ConfigWindow::Width(size as u32),
+                x::ConfigWindow::Height(size as u32),
+            ],
+        });
+        // 32 位的图标背景清成透明，其它深度的图标自己负责背景
+        let geometry = conn.wait_for_reply(conn.send_request(&x::GetGeometry {
+            drawable: x::Drawable::Window(window),
+        }))?;
+        if geometry.depth() == 32 {
+            conn.send_request(&x::ChangeWindowAttributes {
+                window,
+                value_list: &[x::Cw::BackPixel(0)],
+            });
+        }
+
+        let notify = x::ClientMessageEvent::new(
+            window,
+            self.atoms.xembed,
+            x::ClientMessageData::Data32([
+                x::CURRENT_TIME,
+                XEMBED_EMBEDDED_NOTIFY,
+                0,
+                self.bar.resource_id(),
+                XEMBED_VERSION,
+            ]),
+        );
+        conn.send_request(&x::SendEvent {
+            propagate: false,
+            destination: x::SendEventDest::Window(window),
+            event_mask: x::EventMask::NO_EVENT,
+            event: &notify,
+        });
+
+        let mapped = self.wants_mapped(window)?;
+        self.icons.borrow_mut().push(TrayIcon { window, mapped });
+        self.reflow()
+    }
+
+    fn undock(&self, window: x::Window) -> Result<(), MyBarError> {
+        self.icons.borrow_mut().retain(|i| i.window != window);
+        self.reflow()
+    }
+
+    // 按顺序排列可见的图标，然后重画背景
+    fn reflow(&self) -> Result<(), MyBarError> {
+        let size = self.icon_size();
+        let icons = self.icons.borrow();
+        let mapped: Vec<bool> = icons.iter().map(|icon| icon.mapped).collect();
+        let positions = icon_positions(self.right, size, MAX_ICONS, &mapped);
+        for (icon, x) in icons.iter().zip(positions) {
+            let Some(x) = x else {
+                self.conn.send_request(&x::UnmapWindow {
+                    window: icon.window,
+                });
+                continue;
+            };
+            self.conn.send_request(&x::ConfigureWindow {
+                window: icon.window,
+                value_list: &[
+                    x::ConfigWindow::X(x as i32),
+                    x::ConfigWindow::Y((self.y + ICON_MARGIN as i16) as i32),
+                    x::ConfigWindow::Width(size as u32),
+                    x::ConfigWindow::Height(size as u32),
+                ],
+            });
+            self.conn.send_request(&x::MapWindow {
+                window: icon.window,
+            });
+        }
+        drop(icons);
+        self.draw()?;
+        self.painter.flush()
+    }
+}
+
+/// count 个可见图标加上两侧留白的宽度，没有图标时为 0
+fn tray_width(count: u16, size: u16) -> u16 {
+    if count == 0 {
+        return 0;
+    }
+    PADDING * 2 + count * size + (count - 1) * ICON_GAP
+}
+
+/// 右边缘固定在 right，按停靠顺序从左到右排列可见的图标，返回每个图标的 x，
+/// 隐藏的图标和排在前 max 个之后的图标为 None
+fn icon_positions(right: i16, size: u16, max: u16, mapped: &[bool]) -> Vec<Option<i16>> {
+    let count = mapped.iter().filter(|&&m| m).count() as u16;
+    let mut x = right - tray_width(count.min(max), size) as i16 + PADDING as i16;
+    let mut shown = 0;
+    mapped
+        .iter()
+        .map(|&m| {
+            if !m || shown == max {
+                return None;
+            }
+            shown += 1;
+            let position = x;
+            x += (size + ICON_GAP) as i16;
+            Some(position)
+        })
+        .collect()
+}
+
+/// _XEMBED_INFO 是 [version, flags]；没有这个属性或者没有 flags 时按照惯例直接映射
+fn mapped_from_info(info: Option<&[u32]>) -> bool {
+    info.is_none_or(|info| info.get(1).is_none_or(|flags| flags & XEMBED_MAPPED != 0))
+}
+
+impl Component for Tray<'_> {
+    fn name(&self) -> &str {
+        "tray"
+    }
+
+    fn draw(&self) -> Result<(), MyBarError> {
+        let width = self.width();
+        let drawn = self.drawn_width.replace(width);
+        if drawn > width {
+            self.painter
+                .clear_area((self.right - drawn as i16) as f64, drawn as f64)?;
+        }
+        if width == 0 {
+            return Ok(());
+        }
+        // 图标窗口是状态栏的子窗口，这里只画它们后面的背景
+        self.painter
+            .draw_rounded_background(self.x() as f64, width as f64, 10.0, "#475164")
+    }
+
+    fn contains_point(&self, x: i16, y: i16) -> bool {
+        x >= self.x() && x <= self.right && y >= self.y && y <= self.y + self.height as i16
+    }
+
+    fn handle_event(&self, _event: &Event) -> Result<(), MyBarError> {
+        // 图标自己接收点击
+        Ok(())
+    }
+
+    fn get_bounds(&self) -> (i16, i16, u16, u16) {
+        (self.x(), self.y, self.width(), self.height)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let icons: Vec<u32> = self
+            .icons
+            .borrow()
+            .iter()
+            .filter(|icon| icon.mapped)
+            .map(|icon| icon.window.resource_id())
+            .collect();
+        Snapshot::new("", json!({ "icons": icons }))
+    }
+
+    fn handle_x_event(&self, event: &xcb::Event) -> Result<bool, MyBarError> {
+        match event {
+            xcb::Event::X(x::Event::ClientMessage(ev))
+                if ev.window() == self.owner && ev.r#type() == self.atoms.opcode =>
+            {
+                if let x::ClientMessageData::Data32(data) = ev.data()
+                    && data[1] == SYSTEM_TRAY_REQUEST_DOCK
+                {
+                    // 协议里窗口 ID 只能以整数形式出现在消息数据中
+                    let window = unsafe { <x::Window as xcb::XidNew>::new(data[2]) };
+                    if let Err(e) = self.dock(window) {
+                        eprintln!("tray: dock {window:?}: {e}");
+                    }
+                }
+                Ok(true)
+            }
+            xcb::Event::X(x::Event::SelectionClear(ev)) if ev.owner() == self.owner => {
+                eprintln!("tray: lost the system tray selection");
+                Ok(true)
+            }
+            xcb::Event::X(x::Event::DestroyNotify(ev)) if self.is_icon(ev.window()) => {
+                self.undock(ev.window())?;
+                Ok(true)
+            }
+            // 客户端自己把图标拿走了
+            xcb::Event::X(x::Event::ReparentNotify(ev))
+                if self.is_icon(ev.window()) && ev.parent() != self.bar =>
+            {
+                self.undock(ev.window())?;
+                Ok(true)
+            }
+            xcb::Event::X(x::Event::PropertyNotify(ev))
+                if ev.atom() == self.atoms.xembed_info && self.is_icon(ev.window()) =>
+            {
+                let mapped = self.wants_mapped(ev.window())?;
+                for icon in self.icons.borrow_mut().iter_mut() {
+                    if icon.window == ev.window() {
+                        icon.mapped = mapped;
+                    }
+                }
+                self.reflow()?;
+                Ok(true)
+            }
+            // 图标自己改了大小，改回来
+            xcb::Event::X(x::Event::ConfigureNotify(ev)) if self.is_icon(ev.window()) => {
+                let size = self.icon_size();
+                if ev.width() != size || ev.height() != size {
+                    self.reflow()?;
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+impl Drop for Tray<'_> {
+    fn drop(&mut self) {
+        // 把图标还给根窗口，客户端会等待下一个托盘
+        for icon in self.icons.borrow().iter() {
+            self.conn.send_request(&x::UnmapWindow {
+                window: icon.window,
+            });
+            self.conn.send_request(&x::ReparentWindow {
+                window: icon.window,
+                parent: self.root,
+                x: 0,
+                y: 0,
+            });
+        }
+        self.conn
+            .send_request(&x::DestroyWindow { window: self.owner });
+        if let Err(e) = self.conn.flush() {
+            eprintln!("close tray: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{icon_positions, mapped_from_info, tray_width};
+
+    #[test]
+    fn reflow_keeps_right_edge() {
+        assert_eq!(tray_width(0, 24), 0);
+        // 两侧留白 10，图标 24，间距 6
+        assert_eq!(tray_width(2, 24), 74);
+        let positions = icon_positions(810, 24, 3, &[true, false, true]);
+        assert_eq!(positions, vec![Some(746), None, Some(776)]);
+        // 最后一个图标的右边到右边缘正好是留白
+        assert_eq!(776 + 24 + 10, 810);
+
+        // 隐藏的图标重新出现后，前面的图标向左移动
+        let positions = icon_positions(810, 24, 3, &[true, true, true]);
+        assert_eq!(positions, vec![Some(716), Some(746), Some(776)]);
+        assert_eq!(icon_positions(810, 24, 3, &[false]), vec![None]);
+
+        // 超出预留位置的图标不显示，托盘不会再向左扩展
+        let positions = icon_positions(810, 24, 2, &[true, false, true, true]);
+        assert_eq!(positions, vec![Some(746), None, Some(776), None]);
+    }
+
+    #[test]
+    fn xembed_info_flags() {
+        assert!(mapped_from_info(None));
+        assert!(mapped_from_info(Some(&[0])));
+        assert!(mapped_from_info(Some(&[0, 1])));
+        assert!(!mapped_from_info(Some(&[0, 0])));
+    }
+}
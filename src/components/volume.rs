@@ -1,5 +1,8 @@
-use super::{Component, Event, Painter};
+use super::{Component, Event, Painter, Snapshot};
+use crate::alsa::Mixer;
 use crate::error::MyBarError;
+use serde_json::json;
+use std::cell::Cell;
 
 pub struct Volume<'a> {
     x: i16,
@@ -7,26 +10,34 @@ pub struct Volume<'a> {
     width: u16,
     height: u16,
     painter: &'a Painter<'a>,
-    audio: &'a crate::alsa::Audio,
+    audio: &'a dyn Mixer,
+    // 上次画出来的音量和静音状态，供 snapshot 使用
+    drawn: Cell<Option<(f64, bool)>>,
 }
 
 impl<'a> Volume<'a> {
-    pub fn new(painter: &'a Painter, audio: &'a crate::alsa::Audio) -> Self {
+    pub fn new(painter: &'a Painter, audio: &'a dyn Mixer) -> Self {
         Self {
-            x: 1420,
+            x: 893,
             y: 0,
             width: 100,
             height: 40,
             painter,
             audio,
+            drawn: Cell::new(None),
         }
     }
 }
 
 impl<'a> Component for Volume<'a> {
+    fn name(&self) -> &str {
+        "volume"
+    }
+
     fn draw(&self) -> Result<(), MyBarError> {
         let v = self.audio.get_current_volume();
         let unmuted = self.audio.is_unmuted();
+        self.drawn.set(Some((v, !unmuted)));
         // 根据静音状态选择不同的图标和颜色
         let (icon, color) = if unmuted {
             ("", "#ff3399") // 未静音时使用粉色
@@ -62,7 +73,7 @@ impl<'a> Component for Volume<'a> {
 
     fn handle_event(&self, event: &Event) -> Result<(), MyBarError> {
         match event {
-            Event::MouseClick { x, y, button } => {
+            Event::MouseClick { x, y, button, .. } => {
                 let button = *button;
                 if button == 1 {
                     // 左键
@@ -91,6 +102,7 @@ impl<'a> Component for Volume<'a> {
             Event::KeyPress { keycode } => {
                 // TODO: 实现键盘控制音量逻辑
             }
+            _ => {}
         }
         self.painter.flush()?;
         Ok(())
@@ -99,4 +111,52 @@ impl<'a> Component for Volume<'a> {
     fn get_bounds(&self) -> (i16, i16, u16, u16) {
         (self.x, self.y, self.width, self.height)
     }
+
+    fn snapshot(&self) -> Snapshot {
+        let Some((volume, muted)) = self.drawn.get() else {
+            return Snapshot::default();
+        };
+        Snapshot::new(
+            format!("{:.0}%", volume * 100.0),
+            json!({ "volume": volume, "muted": muted }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Volume;
+    use crate::alsa::{FixedMixer, Mixer};
+    use crate::components::{Component, Painter, golden};
+
+    fn check(name: &str, mixer: FixedMixer) {
+        golden::check(name, |painter| {
+            let volume = Volume::new(painter, &mixer);
+            volume.draw()?;
+            Ok(volume.get_bounds())
+        });
+    }
+
+    #[test]
+    fn golden_unmuted() {
+        check("volume-unmuted", FixedMixer::new(0.6, true));
+    }
+
+    #[test]
+    fn golden_muted() {
+        check("volume-muted", FixedMixer::new(0.6, false));
+    }
+
+    #[test]
+    fn snapshot_uses_drawn_state() {
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 1920, 40).unwrap();
+        let painter = Painter::for_surface(&surface, 1920, 40).unwrap();
+        let mixer = FixedMixer::new(0.6, true);
+        let volume = Volume::new(&painter, &mixer);
+        assert_eq!(volume.snapshot().text, "");
+        volume.draw().unwrap();
+        // snapshot 不再去读混音器
+        mixer.set_current_volumn(0.2);
+        assert_eq!(volume.snapshot().text, "60%");
+    }
 }
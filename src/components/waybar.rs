@@ -0,0 +1,231 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use super::{Painter, Snapshot};
+use crate::error::MyBarError;
+use crate::waybar::{self, WaybarOutput};
+
+const COLOR: &str = "#ff3329";
+const BACKGROUND: &str = "#475164";
+const BAR_WIDTH: f64 = 60.0;
+
+/// Script 和 Tail 在 IPC 里的状态就是输出的各个字段
+impl From<WaybarOutput> for Snapshot {
+    fn from(output: WaybarOutput) -> Self {
+        let state = serde_json::to_value(&output).unwrap_or_default();
+        Snapshot::new(output.text, state)
+    }
+}
+
+/// class 对应的前景色和背景色
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassStyle {
+    pub color: String,
+    pub background: String,
+}
+
+/// 按 Waybar 的字段绘制自定义模块的输出：class 决定颜色，
+/// alt 或 percentage 决定图标，percentage 还可以画成进度条
+pub struct WaybarFormat {
+    styles: HashMap<String, ClassStyle>,
+    // alt 对应的图标
+    icons: HashMap<String, String>,
+    // 按 percentage 选择的图标
+    ramp: Vec<String>,
+    bar: bool,
+}
+
+impl Default for WaybarFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WaybarFormat {
+    /// 默认提供 warning、critical 和 urgent 三种 class 的样式
+    pub fn new() -> Self {
+        Self {
+            styles: HashMap::new(),
+            icons: HashMap::new(),
+            ramp: vec![],
+            bar: false,
+        }
+        .with_class("warning", "#ff9900", BACKGROUND)
+        .with_class("critical", "#ff0000", BACKGROUND)
+        .with_class("urgent", "#ff0000", BACKGROUND)
+    }
+
+    pub fn with_class(mut self, class: &str, color: &str, background: &str) -> Self {
+        self.styles.insert(
+            class.to_string(),
+            ClassStyle {
+                color: color.to_string(),
+                background: background.to_string(),
+            },
+        );
+        self
+    }
+
+    pub fn with_icon(mut self, alt: &str, icon: &str) -> Self {
+        self.icons.insert(alt.to_string(), icon.to_string());
+        self
+    }
+
+    pub fn with_ramp(mut self, icons: &[&str]) -> Self {
+        self.ramp = icons.iter().map(|i| i.to_string()).collect();
+        self
+    }
+
+    /// 在文字后面把 percentage 画成进度条
+    pub fn with_bar(mut self, bar: bool) -> Self {
+        self.bar = bar;
+        self
+    }
+
+    /// 第一个配置了样式的 class 生效
+    fn style(&self, output: &WaybarOutput) -> (&str, &str) {
+        output
+            .class
+            .iter()
+            .find_map(|class| self.styles.get(class))
+            .map(|style| (style.color.as_str(), style.background.as_str()))
+            .unwrap_or((COLOR, BACKGROUND))
+    }
+
+    /// alt 在图标表里时优先使用，否则按 percentage 选择
+    fn icon(&self, output: &WaybarOutput) -> Option<&str> {
+        if let Some(icon) = output.alt.as_ref().and_then(|alt| self.icons.get(alt)) {
+            return Some(icon);
+        }
+        waybar::ramp(&self.ramp, output.percentage?).map(|icon| icon.as_str())
+    }
+
+    /// 在 x 处绘制，宽度不超过 max_width；没有内容时擦掉上一次绘制的部分
+    pub fn draw(
+        &self,
+        painter: &Painter,
+        x: f64,
+        max_width: f64,
+        output: &WaybarOutput,
+        drawn_width: &Cell<f64>,
+    ) -> Result<(), MyBarError> {
+        let icon = self.icon(output);
+        if output.text.is_empty() && icon.is_none() {
+            painter.clear_area(x, drawn_width.get())?;
+            drawn_width.set(0.0);
+            return Ok(());
+        }
+        let ratio = output.percentage.filter(|_| self.bar).map(|p| p / 100.0);
+        let (color, background) = self.style(output);
+
+        let mut widths = vec![];
+        if let Some(icon) = icon {
+            widths.push(painter.text_width(icon)?);
+        }
+        if !output.text.is_empty() {
+            widths.push(painter.text_width(&output.text)?);
+        }
+        if ratio.is_some() {
+            widths.push(BAR_WIDTH);
+        }
+        let content: f64 = widths.iter().sum::<f64>() + 5.0 * (widths.len() - 1) as f64;
+        let w = (content + 10.0 * 2.0).min(max_width);
+
+        if drawn_width.get() > w {
+            painter.clear_area(x, drawn_width.get())?;
+        }
+        drawn_width.set(w);
+        painter.draw_rounded_background(x, w, 10.0, background)?;
+
+        // 内容比 max_width 长时截掉超出背景的部分，不画到相邻的组件上
+        let cr = &painter.cairo_conn;
+        cr.save()?;
+        cr.rectangle(x, 0.0, w, painter.height() as f64);
+        cr.clip();
+        let result = draw_content(painter, x + 10.0, icon, &output.text, ratio, color);
+        cr.restore()?;
+        result
+    }
+}
+
+// 依次画图标、文字和进度条
+fn draw_content(
+    painter: &Painter,
+    mut x: f64,
+    icon: Option<&str>,
+    text: &str,
+    ratio: Option<f64>,
+    color: &str,
+) -> Result<(), MyBarError> {
+    if let Some(icon) = icon {
+        painter.draw_text(x, 10.0, icon, color)?;
+        x += painter.text_width(icon)? + 5.0;
+    }
+    if !text.is_empty() {
+        painter.draw_text(x, 10.0, text, color)?;
+        x += painter.text_width(text)? + 5.0;
+    }
+    if let Some(ratio) = ratio {
+        painter.set_hex_color(color)?;
+        painter.cairo_conn.move_to(x, 20.0);
+        painter
+            .cairo_conn
+            .line_to(x + BAR_WIDTH * ratio.clamp(0.0, 1.0), 20.0);
+        painter.cairo_conn.stroke()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::WaybarFormat;
+    use crate::components::golden;
+    use crate::waybar::WaybarOutput;
+
+    #[test]
+    fn class_and_icon_selection() {
+        let format = WaybarFormat::new()
+            .with_class("muted", "#666666", "#222222")
+            .with_icon("muted", "m")
+            .with_ramp(&["low", "high"]);
+        let output = WaybarOutput {
+            text: "40%".to_string(),
+            class: vec!["unknown".to_string(), "muted".to_string()],
+            percentage: Some(80.0),
+            ..Default::default()
+        };
+        assert_eq!(format.style(&output), ("#666666", "#222222"));
+        assert_eq!(format.icon(&output), Some("high"));
+
+        let output = WaybarOutput {
+            alt: Some("muted".to_string()),
+            ..output
+        };
+        assert_eq!(format.icon(&output), Some("m"));
+        assert_eq!(
+            format.style(&WaybarOutput::plain("x")),
+            ("#ff3329", "#475164")
+        );
+    }
+
+    #[test]
+    fn golden_clipped_to_max_width() {
+        let output = WaybarOutput {
+            text: "a line much longer than the module".to_string(),
+            percentage: Some(50.0),
+            ..Default::default()
+        };
+        golden::check("waybar-clipped", |painter| {
+            WaybarFormat::new().with_bar(true).draw(
+                painter,
+                100.0,
+                120.0,
+                &output,
+                &Cell::new(0.0),
+            )?;
+            Ok((100, 0, 120, 40))
+        });
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::MyResult;
+
+/// /proc/stat 中一行 cpu 的累计时间（单位 jiffies）
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CpuTimes {
+    idle: u64,
+    total: u64,
+}
+
+/// 两次采样之间的使用率，取值 0.0 - 1.0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuUsage {
+    pub total: f64,
+    pub cores: Vec<f64>,
+}
+
+/// 解析 /proc/stat，第一项是汇总的 cpu 行，之后是 cpu0、cpu1 ...
+fn parse_stat(text: &str) -> Vec<CpuTimes> {
+    text.lines()
+        .filter(|line| line.starts_with("cpu"))
+        .map(|line| {
+            let fields: Vec<u64> = line
+                .split_whitespace()
+                .skip(1)
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            // user nice system idle iowait irq softirq steal guest guest_nice，
+            // guest 已经包含在 user 中，不重复计算
+            let total = fields.iter().take(8).sum();
+            let idle = fields.get(3).copied().unwrap_or(0) + fields.get(4).copied().unwrap_or(0);
+            CpuTimes { idle, total }
+        })
+        .collect()
+}
+
+fn usage(previous: CpuTimes, current: CpuTimes) -> f64 {
+    let total = current.total.saturating_sub(previous.total);
+    let idle = current.idle.saturating_sub(previous.idle);
+    if total == 0 {
+        return 0.0;
+    }
+    (total.saturating_sub(idle)) as f64 / total as f64
+}
+
+/// 通过相邻两次读取 /proc/stat 的差值计算使用率
+pub struct CpuStat {
+    root: PathBuf,
+    previous: Vec<CpuTimes>,
+}
+
+impl Default for CpuStat {
+    fn default() -> Self {
+        Self::new("/proc")
+    }
+}
+
+impl CpuStat {
+    /// root 一般是 /proc，测试时可以指向一个假的目录
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            previous: vec![],
+        }
+    }
+
+    /// 采样一次，第一次调用时没有可比较的数据，返回 None
+    pub fn sample(&mut self) -> MyResult<Option<CpuUsage>> {
+        let text = fs::read_to_string(self.root.join("stat"))?;
+        let current = parse_stat(&text);
+        let previous = std::mem::replace(&mut self.previous, current.clone());
+        // 第一次采样，或者 CPU 热插拔导致核心数变了
+        if previous.len() != current.len() || current.is_empty() {
+            return Ok(None);
+        }
+        let mut usages = previous.iter().zip(&current).map(|(p, c)| usage(*p, *c));
+        let total = usages.next().unwrap_or(0.0);
+        Ok(Some(CpuUsage {
+            total,
+            cores: usages.collect(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::CpuStat;
+
+    #[test]
+    fn sample_deltas() {
+        let root = std::env::temp_dir().join(format!("mybar-cpu-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let stat = root.join("stat");
+        fs::write(
+            &stat,
+            "cpu  100 0 100 800 0 0 0 0 0 0\n\
+             cpu0 50 0 50 400 0 0 0 0 0 0\n\
+             cpu1 50 0 50 400 0 0 0 0 0 0\n\
+             intr 12345\n",
+        )
+        .unwrap();
+        let mut cpu = CpuStat::new(&root);
+        assert_eq!(cpu.sample().unwrap(), None);
+
+        // cpu0 全忙，cpu1 全闲
+        fs::write(
+            &stat,
+            "cpu  200 0 200 900 0 0 0 0 0 0\n\
+             cpu0 150 0 150 400 0 0 0 0 0 0\n\
+             cpu1 50 0 50 500 0 0 0 0 0 0\n",
+        )
+        .unwrap();
+        let usage = cpu.sample().unwrap().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!((usage.total - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(usage.cores, vec![1.0, 0.0]);
+    }
+}
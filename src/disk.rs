@@ -0,0 +1,127 @@
+use std::ffi::CString;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{MyBarError, MyResult};
+
+// 这些文件系统不占用磁盘，自动发现挂载点时跳过
+const VIRTUAL_FS: &[&str] = &["squashfs", "overlay", "tmpfs", "devtmpfs", "ramfs"];
+
+/// 一个文件系统的容量，单位字节
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskUsage {
+    pub total: u64,
+    /// 普通用户可用的空间，不含为 root 保留的部分
+    pub available: u64,
+    pub free: u64,
+}
+
+impl DiskUsage {
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    /// 和 df 一样按 used / (used + available) 计算使用率
+    pub fn used_ratio(&self) -> f64 {
+        let size = self.used() + self.available;
+        if size == 0 {
+            return 0.0;
+        }
+        self.used() as f64 / size as f64
+    }
+}
+
+pub fn statvfs(mount: &str) -> MyResult<DiskUsage> {
+    let path = CString::new(mount).map_err(|e| MyBarError::Other(e.to_string()))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let block = stat.f_frsize as u64;
+    Ok(DiskUsage {
+        total: stat.f_blocks as u64 * block,
+        available: stat.f_bavail as u64 * block,
+        free: stat.f_bfree as u64 * block,
+    })
+}
+
+// /proc/self/mounts 用 \040 这样的八进制转义空格等字符
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        if let Some(v) = escaped {
+            out.push(v);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// 从 mounts 文件中挑出块设备上的挂载点
+fn parse_mounts(text: &str) -> Vec<String> {
+    let mut mounts = vec![];
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [device, mount, fs_type, ..] = fields[..] else {
+            continue;
+        };
+        if !device.starts_with("/dev/") || VIRTUAL_FS.contains(&fs_type) {
+            continue;
+        }
+        let mount = unescape(mount);
+        // 同一个设备可能挂载多次（例如 btrfs 子卷），只保留第一次出现的挂载点
+        if !mounts.contains(&mount) {
+            mounts.push(mount);
+        }
+    }
+    mounts
+}
+
+/// 读取 /proc/self/mounts 自动发现挂载点
+pub fn discover_mounts(proc_root: &Path) -> MyResult<Vec<String>> {
+    Ok(parse_mounts(&fs::read_to_string(
+        proc_root.join("self/mounts"),
+    )?))
+}
+
+/// 把字节数格式化成 "512M"、"12.3G"
+pub fn format_size(bytes: u64) -> String {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    let bytes = bytes as f64;
+    if bytes < GIB {
+        format!("{:.0}M", bytes / 1024.0 / 1024.0)
+    } else if bytes < GIB * 1024.0 {
+        format!("{:.1}G", bytes / GIB)
+    } else {
+        format!("{:.1}T", bytes / GIB / 1024.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_size, parse_mounts};
+
+    #[test]
+    fn parse_proc_mounts() {
+        let mounts = parse_mounts(
+            "/dev/nvme0n1p2 / ext4 rw,relatime 0 0\n\
+             proc /proc proc rw,nosuid 0 0\n\
+             tmpfs /tmp tmpfs rw 0 0\n\
+             /dev/nvme0n1p1 /boot/efi vfat rw 0 0\n\
+             /dev/sda1 /mnt/My\\040Disk ext4 rw 0 0\n\
+             /dev/loop0 /snap/core/1 squashfs ro 0 0\n",
+        );
+        assert_eq!(mounts, vec!["/", "/boot/efi", "/mnt/My Disk"]);
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024 / 2), "1.5G");
+    }
+}
@@ -6,6 +6,7 @@ pub enum MyBarError {
     Xcb(xcb::Error),
     XcbConn(xcb::ConnError),
     XcbProto(xcb::ProtocolError),
+    Io(std::io::Error),
     Other(String),
 }
 
@@ -19,6 +20,7 @@ impl fmt::Display for MyBarError {
             MyBarError::Other(s) => write!(f, "Other error: {}", s),
             MyBarError::XcbConn(e) => write!(f, "Xcb connect error: {}", e),
             MyBarError::XcbProto(e) => write!(f, "Xcb protocol error: {}", e),
+            MyBarError::Io(e) => write!(f, "IO error: {}", e),
         }
     }
 }
@@ -48,3 +50,9 @@ impl From<xcb::ProtocolError> for MyBarError {
         MyBarError::XcbProto(value)
     }
 }
+
+impl From<std::io::Error> for MyBarError {
+    fn from(value: std::io::Error) -> Self {
+        MyBarError::Io(value)
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MyBarError, MyResult};
+
+/// i3bar 协议的第一行
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Header {
+    pub version: u32,
+    pub click_events: bool,
+}
+
+impl Header {
+    pub fn parse(line: &str) -> MyResult<Self> {
+        serde_json::from_str(line.trim()).map_err(|e| MyBarError::Other(e.to_string()))
+    }
+}
+
+/// min_width 可以是像素，也可以是一段文字（按这段文字的宽度）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum MinWidth {
+    Pixels(f64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// 状态行里的一个块
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Block {
+    pub full_text: String,
+    pub color: Option<String>,
+    pub background: Option<String>,
+    pub separator: bool,
+    pub separator_block_width: f64,
+    pub min_width: Option<MinWidth>,
+    pub align: Align,
+    pub urgent: bool,
+    pub name: Option<String>,
+    pub instance: Option<String>,
+    /// "pango" 时 full_text 里带有标签
+    pub markup: Option<String>,
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Self {
+            full_text: String::new(),
+            color: None,
+            background: None,
+            // 协议规定的默认值
+            separator: true,
+            separator_block_width: 9.0,
+            min_width: None,
+            align: Align::Left,
+            urgent: false,
+            name: None,
+            instance: None,
+            markup: None,
+        }
+    }
+}
+
+impl Block {
+    /// 要显示的文字，去掉 pango 标签
+    pub fn text(&self) -> String {
+        match self.markup.as_deref() {
+            Some("pango") => strip_markup(&self.full_text),
+            _ => self.full_text.clone(),
+        }
+    }
+}
+
+/// 协议里的颜色是 #RRGGBB 或 #RRGGBBAA，转换成 Painter 使用的 #RRGGBB 或 #AARRGGBB，
+/// 无效的颜色返回 None
+pub fn to_argb(color: &str) -> Option<String> {
+    let hex = color.strip_prefix('#')?;
+    if !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        6 => Some(color.to_string()),
+        8 => Some(format!("#{}{}", &hex[6..], &hex[..6])),
+        _ => None,
+    }
+}
+
+/// 解析头部之后的一行
+///
+/// 状态是一个无限长的数组，生产者每行输出其中一个元素：第一行是 "["，之后是
+/// "[{...}]," 或 ",[{...}]"。不是状态行的行返回 None。
+pub fn parse_line(line: &str) -> MyResult<Option<Vec<Block>>> {
+    let line = line.trim();
+    let line = line.strip_prefix(',').unwrap_or(line).trim_start();
+    let line = line.strip_suffix(',').unwrap_or(line).trim_end();
+    if line.is_empty() || line == "[" || line == "]" {
+        return Ok(None);
+    }
+    serde_json::from_str(line)
+        .map(Some)
+        .map_err(|e| MyBarError::Other(e.to_string()))
+}
+
+// 只去掉标签和常见的实体，不处理样式
+fn strip_markup(text: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// 写回生产者 stdin 的点击事件
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ClickEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    pub button: u8,
+    pub x: i32,
+    pub y: i32,
+    pub relative_x: i32,
+    pub relative_y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// 点击事件也是一个无限长的数组："[" 开头，事件之间用逗号分隔
+pub struct ClickWriter<W: Write> {
+    writer: W,
+    first: bool,
+}
+
+impl<W: Write> ClickWriter<W> {
+    pub fn new(mut writer: W) -> MyResult<Self> {
+        writer.write_all(b"[\n")?;
+        writer.flush()?;
+        Ok(Self {
+            writer,
+            first: true,
+        })
+    }
+
+    pub fn send(&mut self, event: &ClickEvent) -> MyResult<()> {
+        let json = serde_json::to_string(event).map_err(|e| MyBarError::Other(e.to_string()))?;
+        let separator = if self.first { "" } else { "," };
+        self.first = false;
+        writeln!(self.writer, "{separator}{json}")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Align, ClickEvent, ClickWriter, Header, MinWidth, parse_line, to_argb};
+
+    #[test]
+    fn rgba_to_argb() {
+        assert_eq!(to_argb("#ff0000").as_deref(), Some("#ff0000"));
+        assert_eq!(to_argb("#11223380").as_deref(), Some("#80112233"));
+        assert_eq!(to_argb("red"), None);
+        assert_eq!(to_argb("#ff00"), None);
+        assert_eq!(to_argb("#gg0000"), None);
+    }
+
+    #[test]
+    fn parse_i3bar_stream() {
+        let header = Header::parse(r#"{"version":1,"click_events":true}"#).unwrap();
+        assert!(header.click_events);
+        assert_eq!(parse_line("[").unwrap(), None);
+
+        let blocks = parse_line(
+            r##"[{"full_text":"E: down","color":"#ff0000","separator":false,"min_width":"E: 100.0.0.0","name":"eth"},{"full_text":"<b>50%</b> &amp; up","markup":"pango","align":"center","min_width":80}],"##,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].color.as_deref(), Some("#ff0000"));
+        assert!(!blocks[0].separator);
+        assert_eq!(
+            blocks[0].min_width,
+            Some(MinWidth::Text("E: 100.0.0.0".to_string()))
+        );
+        assert!(blocks[1].separator);
+        assert_eq!(blocks[1].align, Align::Center);
+        assert_eq!(blocks[1].min_width, Some(MinWidth::Pixels(80.0)));
+        assert_eq!(blocks[1].text(), "50% & up");
+
+        let blocks = parse_line(r#",[{"full_text":"ok"}]"#).unwrap().unwrap();
+        assert_eq!(blocks[0].full_text, "ok");
+
+        let mut out = vec![];
+        let mut writer = ClickWriter::new(&mut out).unwrap();
+        let event = ClickEvent {
+            name: Some("eth".to_string()),
+            button: 1,
+            ..Default::default()
+        };
+        writer.send(&event).unwrap();
+        writer.send(&event).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "[");
+        assert!(lines[1].starts_with(r#"{"name":"eth","button":1,"#));
+        assert!(lines[2].starts_with(",{"));
+    }
+}
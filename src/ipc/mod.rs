@@ -0,0 +1,7 @@
+pub mod protocol;
+mod server;
+mod state;
+
+pub use protocol::{Event, Reply, Request};
+pub use server::{Server, reply};
+pub use state::{Watcher, dump};
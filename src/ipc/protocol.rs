@@ -0,0 +1,198 @@
+// 状态栏和 mybar-msg 共用的协议，mybar-msg 通过 #[path] 引入，所以这里不能引用 crate 里的其它模块
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// subscribe 可以订阅的事件类型，和 Event 的 type 字段一致
+pub const EVENT_TYPES: [&str; 4] = ["desktop", "volume", "update", "click"];
+
+/// mybar-msg 发给状态栏的命令，每个连接发一行
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Hide,
+    Show,
+    Toggle,
+    /// 重新执行状态栏程序
+    Reload,
+    /// 立即刷新某个组件
+    Update(String),
+    /// 触发组件的某个动作，例如 action volume left
+    Action(String, String),
+    /// 之后在这个连接上持续推送事件，只推送列出的类型，不列出时推送所有类型
+    Subscribe(Vec<String>),
+    /// 所有组件当前的内容、位置和状态
+    State,
+}
+
+impl Request {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["hide"] => Ok(Request::Hide),
+            ["show"] => Ok(Request::Show),
+            ["toggle"] => Ok(Request::Toggle),
+            ["reload"] => Ok(Request::Reload),
+            ["update", module] => Ok(Request::Update(module.to_string())),
+            ["action", module, name] => Ok(Request::Action(module.to_string(), name.to_string())),
+            ["subscribe", ref types @ ..] => {
+                if let Some(unknown) = types.iter().find(|t| !EVENT_TYPES.contains(t)) {
+                    return Err(format!(
+                        "unknown event type {unknown}, expected one of {}",
+                        EVENT_TYPES.join(", ")
+                    ));
+                }
+                Ok(Request::Subscribe(
+                    types.iter().map(|t| t.to_string()).collect(),
+                ))
+            }
+            ["state"] => Ok(Request::State),
+            [] => Err("empty command".to_string()),
+            _ => Err(format!("invalid command: {}", line.trim())),
+        }
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Request::Hide => write!(f, "hide"),
+            Request::Show => write!(f, "show"),
+            Request::Toggle => write!(f, "toggle"),
+            Request::Reload => write!(f, "reload"),
+            Request::Update(module) => write!(f, "update {module}"),
+            Request::Action(module, name) => write!(f, "action {module} {name}"),
+            Request::Subscribe(types) if types.is_empty() => write!(f, "subscribe"),
+            Request::Subscribe(types) => write!(f, "subscribe {}", types.join(" ")),
+            Request::State => write!(f, "state"),
+        }
+    }
+}
+
+/// 状态栏对每条命令的回复，一行 JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reply {
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// state 命令的结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl Reply {
+    pub fn with_data(data: Value) -> Self {
+        Self {
+            success: true,
+            error: None,
+            data: Some(data),
+        }
+    }
+}
+
+impl From<Result<(), String>> for Reply {
+    fn from(result: Result<(), String>) -> Self {
+        Self {
+            success: result.is_ok(),
+            error: result.err(),
+            data: None,
+        }
+    }
+}
+
+/// subscribe 之后推送的事件，每行一个 JSON 对象
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Event {
+    /// bspwm 的桌面状态变了，state 和 bspwm 组件的状态相同
+    Desktop { state: Value },
+    /// 音量或静音状态变了
+    Volume { state: Value },
+    /// 组件显示的内容变了
+    Update {
+        module: String,
+        text: String,
+        state: Value,
+    },
+    /// 组件被点击
+    Click {
+        module: String,
+        x: i16,
+        y: i16,
+        button: u8,
+    },
+}
+
+impl Event {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Desktop { .. } => "desktop",
+            Event::Volume { .. } => "volume",
+            Event::Update { .. } => "update",
+            Event::Click { .. } => "click",
+        }
+    }
+}
+
+/// $XDG_RUNTIME_DIR/mybar/<display>.sock，没有 XDG_RUNTIME_DIR 时放在 /tmp/mybar-<uid>/ 下
+pub fn socket_path() -> PathBuf {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) => PathBuf::from(runtime).join("mybar"),
+        None => PathBuf::from(format!("/tmp/mybar-{}", unsafe { libc::getuid() })),
+    };
+    // DISPLAY 可能带有 / （例如 macOS 上的 launchd 路径）
+    let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+    dir.join(format!("{}.sock", display.replace('/', "_")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Event, Reply, Request};
+
+    #[test]
+    fn parse_requests() {
+        for line in [
+            "hide",
+            "toggle",
+            "update cpu",
+            "action volume scroll-up",
+            "subscribe",
+            "subscribe desktop click",
+            "state",
+        ] {
+            assert_eq!(Request::parse(line).unwrap().to_string(), line);
+        }
+        assert_eq!(
+            Request::parse("  update   cpu\n").unwrap(),
+            Request::Update("cpu".to_string())
+        );
+        assert!(Request::parse("update").is_err());
+        assert!(Request::parse("").is_err());
+        assert!(Request::parse("subscribe desktop mouse").is_err());
+
+        let reply = serde_json::to_string(&Reply::from(Ok(()))).unwrap();
+        assert_eq!(reply, r#"{"success":true}"#);
+        let reply: Reply =
+            serde_json::from_str(r#"{"success":false,"error":"no module named foo"}"#).unwrap();
+        assert_eq!(reply.error.as_deref(), Some("no module named foo"));
+    }
+
+    #[test]
+    fn event_type_matches_kind() {
+        let event = Event::Click {
+            module: "volume".to_string(),
+            x: 10,
+            y: 20,
+            button: 1,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], event.kind());
+        assert_eq!(json["module"], "volume");
+
+        let event = Event::Desktop {
+            state: serde_json::json!({ "monitors": [] }),
+        };
+        assert_eq!(serde_json::to_value(&event).unwrap()["type"], "desktop");
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::protocol::{self, Event, Reply, Request};
+use crate::error::{MyBarError, MyResult};
+use crate::message::Message;
+use xcb::x;
+
+// 客户端连上之后必须在这段时间内发出命令，避免卡住后面的客户端
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+// 推送事件在 X 线程上进行，读得太慢的订阅者会被断开
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+struct Subscriber {
+    // 为空表示所有类型
+    types: Vec<String>,
+    stream: UnixStream,
+}
+
+impl Subscriber {
+    fn wants(&self, kind: &str) -> bool {
+        self.types.is_empty() || self.types.iter().any(|t| t == kind)
+    }
+}
+
+/// 监听控制 socket
+///
+/// 组件只能在 X 线程上使用，所以后台线程收到命令后只是放进队列，
+/// 再用 Message::Ipc 唤醒 X 线程，由 X 线程执行并回复。
+/// subscribe 不需要访问组件，后台线程直接把连接加入订阅者列表。
+pub struct Server {
+    path: PathBuf,
+    requests: Receiver<(Request, UnixStream)>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl Server {
+    pub fn listen(conn: &Arc<xcb::Connection>, window: x::Window) -> MyResult<Self> {
+        let path = protocol::socket_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // 能连上说明另一个状态栏正在使用；连不上就是上次没有清理掉的文件
+        if UnixStream::connect(&path).is_ok() {
+            return Err(MyBarError::Other(format!(
+                "{} is already in use",
+                path.display()
+            )));
+        }
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+
+        let (sender, requests) = mpsc::channel();
+        let subscribers = Arc::new(Mutex::new(vec![]));
+        let conn = Arc::clone(conn);
+        let subscribers_clone = Arc::clone(&subscribers);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        eprintln!("ipc: accept: {e}");
+                        continue;
+                    }
+                };
+                match read_request(&stream) {
+                    Ok(Request::Subscribe(types)) => {
+                        if let Err(e) = stream.set_write_timeout(Some(WRITE_TIMEOUT)) {
+                            reply(&mut stream, Err(e.to_string()));
+                            continue;
+                        }
+                        reply(&mut stream, Ok(()));
+                        if let Ok(mut subscribers) = subscribers_clone.lock() {
+                            subscribers.push(Subscriber { types, stream });
+                        }
+                    }
+                    Ok(request) => {
+                        if sender.send((request, stream)).is_err() {
+                            break;
+                        }
+                        if let Err(e) = Message::Ipc.send(&conn, window) {
+                            eprintln!("ipc: send message: {e}");
+                        }
+                    }
+                    Err(e) => reply(&mut stream, Err(e)),
+                }
+            }
+        });
+        Ok(Self {
+            path,
+            requests,
+            subscribers,
+        })
+    }
+
+    /// 取出所有等待执行的命令，在 X 线程上调用
+    pub fn requests(&self) -> impl Iterator<Item = (Request, UnixStream)> + '_ {
+        self.requests.try_iter()
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.subscribers.lock().is_ok_and(|s| !s.is_empty())
+    }
+
+    /// 是否有订阅者关心这种事件，没有时可以省掉生成事件的开销
+    pub fn wants(&self, kind: &str) -> bool {
+        self.subscribers
+            .lock()
+            .is_ok_and(|s| s.iter().any(|subscriber| subscriber.wants(kind)))
+    }
+
+    /// 把事件推送给订阅了这种事件的客户端，写失败的客户端会被移除
+    pub fn publish(&self, event: &Event) {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return;
+        };
+        let line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("ipc: encode event: {e}");
+                return;
+            }
+        };
+        subscribers.retain_mut(|subscriber| {
+            !subscriber.wants(event.kind()) || writeln!(subscriber.stream, "{line}").is_ok()
+        });
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn read_request(stream: &UnixStream) -> Result<Request, String> {
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    Request::parse(&line)
+}
+
+/// 把执行结果写回客户端
+pub fn reply(stream: &mut UnixStream, reply: impl Into<Reply>) {
+    let reply = match serde_json::to_string(&reply.into()) {
+        Ok(reply) => reply,
+        Err(e) => {
+            eprintln!("ipc: encode reply: {e}");
+            return;
+        }
+    };
+    if let Err(e) = writeln!(stream, "{reply}") {
+        eprintln!("ipc: reply: {e}");
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+use super::Server;
+use super::protocol::Event;
+use crate::components::{Component, Snapshot};
+
+// 组件没有统一的“内容变了”的通知，所以定期比较它们的 snapshot
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 把组件内容的变化转换成 update 事件，bspwm 和 volume 组件的变化另外作为 desktop 和 volume 事件
+pub struct Watcher {
+    last: Vec<Snapshot>,
+    polled: Instant,
+}
+
+impl Watcher {
+    pub fn new() -> Self {
+        Self {
+            last: vec![],
+            polled: Instant::now(),
+        }
+    }
+
+    /// 在主循环里每收到一个事件调用一次，距离上次比较不到 POLL_INTERVAL 时什么也不做
+    pub fn poll(&mut self, server: &Server, components: &[Box<dyn Component + '_>]) {
+        if self.polled.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        self.polled = Instant::now();
+        // 没有订阅者时不生成 snapshot，新的订阅者以它订阅之后的第一次比较为起点
+        if !server.has_subscribers() {
+            self.last.clear();
+            return;
+        }
+        let snapshots: Vec<Snapshot> = components.iter().map(|c| c.snapshot()).collect();
+        if self.last.len() == snapshots.len() {
+            for ((component, old), new) in components.iter().zip(&self.last).zip(&snapshots) {
+                if old != new {
+                    publish(server, component.name(), new);
+                }
+            }
+        }
+        self.last = snapshots;
+    }
+}
+
+fn publish(server: &Server, module: &str, snapshot: &Snapshot) {
+    let state = snapshot.state.clone();
+    match module {
+        "bspwm" => server.publish(&Event::Desktop { state }),
+        "volume" => server.publish(&Event::Volume { state }),
+        _ => {}
+    }
+    server.publish(&Event::Update {
+        module: module.to_string(),
+        text: snapshot.text.clone(),
+        state: snapshot.state.clone(),
+    });
+}
+
+/// state 命令的结果：按组件顺序列出名字、位置、文字和状态
+pub fn dump(components: &[Box<dyn Component + '_>]) -> Value {
+    let modules: Vec<Value> = components
+        .iter()
+        .map(|component| {
+            let (x, y, width, height) = component.get_bounds();
+            let snapshot = component.snapshot();
+            json!({
+                "name": component.name(),
+                "bounds": { "x": x, "y": y, "width": width, "height": height },
+                "text": snapshot.text,
+                "state": snapshot.state,
+            })
+        })
+        .collect();
+    Value::Array(modules)
+}
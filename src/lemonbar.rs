@@ -0,0 +1,202 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// %{A<button>:command:} 定义的点击区域
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action {
+    pub button: u8,
+    pub command: String,
+}
+
+/// 一段文字当时生效的格式
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub align: Align,
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+    pub underline: bool,
+    pub underline_color: Option<String>,
+    /// 嵌套的点击区域，最里层的在最后
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub text: String,
+    pub style: Style,
+}
+
+/// 解析一行 lemonbar 格式的输入
+///
+/// 支持 %{l} %{c} %{r} 对齐，%{F#..} %{B#..} 颜色（"-" 恢复默认），%{R} 交换前景和背景，
+/// %{U#..} 下划线颜色和 %{+u} %{-u} %{!u} 开关，以及 %{A:cmd:}..%{A} 点击区域。
+/// 一个 %{} 里可以用空格分隔多个标签，例如 %{r F#ff0000}。
+pub fn parse(line: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut style = Style::default();
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' || chars.peek() != Some(&'{') {
+            text.push(c);
+            continue;
+        }
+        chars.next();
+        if !text.is_empty() {
+            segments.push(Segment {
+                text: std::mem::take(&mut text),
+                style: style.clone(),
+            });
+        }
+        parse_tags(&mut chars, &mut style);
+    }
+    if !text.is_empty() {
+        segments.push(Segment { text, style });
+    }
+    segments
+}
+
+// 解析 %{ 之后直到 } 的标签
+fn parse_tags(chars: &mut Peekable<Chars>, style: &mut Style) {
+    while let Some(c) = chars.next() {
+        match c {
+            '}' => return,
+            'l' => style.align = Align::Left,
+            'c' => style.align = Align::Center,
+            'r' => style.align = Align::Right,
+            'F' => style.foreground = color(chars),
+            'B' => style.background = color(chars),
+            'U' => style.underline_color = color(chars),
+            'R' => std::mem::swap(&mut style.foreground, &mut style.background),
+            '+' | '-' | '!' if chars.next_if_eq(&'u').is_some() => {
+                style.underline = match c {
+                    '+' => true,
+                    '-' => false,
+                    _ => !style.underline,
+                };
+            }
+            'A' => {
+                let button = chars
+                    .next_if(|c| c.is_ascii_digit())
+                    .and_then(|c| c.to_digit(10))
+                    .unwrap_or(1) as u8;
+                // 没有命令的 %{A} 结束最里层的点击区域
+                if chars.next_if_eq(&':').is_some() {
+                    let command = action_command(chars);
+                    style.actions.push(Action { button, command });
+                } else {
+                    style.actions.pop();
+                }
+            }
+            // 不支持的标签（例如 %{O} %{S}）整个跳过
+            _ if !c.is_whitespace() => {
+                token(chars);
+            }
+            _ => {}
+        }
+    }
+}
+
+// 读到空格或 } 为止
+fn token(chars: &mut Peekable<Chars>) -> String {
+    let mut token = String::new();
+    while let Some(c) = chars.next_if(|c| *c != ' ' && *c != '}') {
+        token.push(c);
+    }
+    token
+}
+
+// "-" 表示恢复默认；#RGB、#ARGB 展开成 #RRGGBB、#AARRGGBB，和 util::hex_to_argb 兼容，
+// 无法解析的颜色也按默认处理
+fn color(chars: &mut Peekable<Chars>) -> Option<String> {
+    let color = token(chars);
+    let hex = color.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        3 | 4 => Some(hex.chars().fold("#".to_string(), |mut s, c| {
+            s.push(c);
+            s.push(c);
+            s
+        })),
+        6 | 8 => Some(color),
+        _ => None,
+    }
+}
+
+// 命令以未转义的 : 结束，\: 表示命令里的冒号
+fn action_command(chars: &mut Peekable<Chars>) -> String {
+    let mut command = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&':') => command.push(chars.next().unwrap_or(':')),
+            ':' => break,
+            _ => command.push(c),
+        }
+    }
+    command
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Action, Align, parse};
+
+    #[test]
+    fn parse_lemonbar_tags() {
+        let segments = parse(
+            "%{l}ws %{F#f00 B#202020}1%{F- B-} %{c}%{+u U#00ff00}%{A:notify-send a\\:b:}%{A3:xdg-open .:}mid%{A}x%{A}%{-u}%{r}%{A2}ignored 100%",
+        );
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["ws ", "1", " ", "mid", "x", "ignored 100%"]);
+
+        assert_eq!(segments[1].style.foreground.as_deref(), Some("#ff0000"));
+        assert_eq!(segments[1].style.background.as_deref(), Some("#202020"));
+        assert_eq!(segments[2].style.foreground, None);
+
+        let mid = &segments[3].style;
+        assert_eq!(mid.align, Align::Center);
+        assert!(mid.underline);
+        assert_eq!(mid.underline_color.as_deref(), Some("#00ff00"));
+        assert_eq!(
+            mid.actions,
+            vec![
+                Action {
+                    button: 1,
+                    command: "notify-send a:b".to_string()
+                },
+                Action {
+                    button: 3,
+                    command: "xdg-open .".to_string()
+                },
+            ]
+        );
+        assert_eq!(segments[4].style.actions.len(), 1);
+
+        let right = &segments[5].style;
+        assert_eq!(right.align, Align::Right);
+        assert!(!right.underline);
+        assert!(right.actions.is_empty());
+    }
+
+    #[test]
+    fn parse_lemonbar_colors() {
+        let segments = parse("%{F#8f00}a%{F#80ff0000}b%{F#ff000}c%{F#xyz}d%{Fred}e");
+        let colors: Vec<Option<&str>> = segments
+            .iter()
+            .map(|s| s.style.foreground.as_deref())
+            .collect();
+        assert_eq!(
+            colors,
+            vec![Some("#88ff0000"), Some("#80ff0000"), None, None, None]
+        );
+    }
+}
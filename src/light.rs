@@ -1,36 +1,94 @@
 use ddc_hi::{Ddc, Display};
-pub fn get_light() -> u16 {
-    // for mut display in Display::enumerate() {
-    //     display.update_capabilities().unwrap();
-    //     if let Some(feature) = display.info.mccs_database.get(0x10) {
-    //         if let Ok(value) = display.handle.get_vcp_feature(feature.code) {
-    //             return value.value();
-    //         }
-    //     }
-    // }
-    0
+
+use crate::error::{MyBarError, MyResult};
+
+// MCCS 亮度特性码
+const BRIGHTNESS: u8 = 0x10;
+
+/// 一台支持 DDC/CI 的显示器及其缓存的亮度
+///
+/// DDC 读写很慢（每次几十毫秒），所以亮度只在枚举时读取一次，之后由写入更新缓存。
+/// 显示器的句柄在 [`Handles`] 里，由后台线程持有。
+pub struct LightDisplay {
+    pub id: String,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub brightness: u16,
+    pub max: u16,
 }
 
-pub fn set_light(v: u16) {
-    for (i, mut display) in Display::enumerate().into_iter().enumerate() {
-        println!("i: {i}");
-        display.update_capabilities().unwrap();
-        if let Some(feature) = display.info.mccs_database.get(0x10) {
-            display.handle.set_vcp_feature(feature.code, v).unwrap();
+impl LightDisplay {
+    /// 用于显示和匹配的名称：型号优先，其次序列号，最后是后端 id
+    pub fn label(&self) -> &str {
+        self.model
+            .as_deref()
+            .or(self.serial.as_deref())
+            .unwrap_or(&self.id)
+    }
+
+    /// 按 label、型号或序列号匹配
+    pub fn matches(&self, name: &str) -> bool {
+        self.label() == name
+            || self.model.as_deref() == Some(name)
+            || self.serial.as_deref() == Some(name)
+    }
+
+    /// 亮度百分比 0 - 100
+    pub fn percent(&self) -> u16 {
+        if self.max == 0 {
+            return 0;
         }
+        (self.brightness as u32 * 100 / self.max as u32) as u16
+    }
+
+    /// 更新缓存的亮度，返回要用 [`Handles::write`] 写入的值
+    pub fn set_percent(&mut self, percent: u16) -> u16 {
+        self.brightness = (percent.min(100) as u32 * self.max as u32 / 100) as u16;
+        self.brightness
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::time::Instant;
+/// 和 enumerate 返回的显示器一一对应的 DDC 句柄
+pub struct Handles(Vec<Display>);
 
-    use super::set_light;
+impl Handles {
+    /// 把第 index 台显示器的亮度设置成 value（原始值，不是百分比）
+    pub fn write(&mut self, index: usize, value: u16) -> MyResult<()> {
+        let display = self
+            .0
+            .get_mut(index)
+            .ok_or_else(|| MyBarError::Other(format!("no display {index}")))?;
+        display
+            .handle
+            .set_vcp_feature(BRIGHTNESS, value)
+            .map_err(|e| MyBarError::Other(format!("set brightness of {}: {e}", display.info.id)))
+    }
+}
 
-    #[test]
-    pub fn t1() {
-        let start = Instant::now();
-        set_light(20);
-        println!("{:?}", start.elapsed());
+/// 枚举所有能读取亮度的显示器
+pub fn enumerate() -> (Vec<LightDisplay>, Handles) {
+    let mut displays = vec![];
+    let mut handles = vec![];
+    for mut display in Display::enumerate() {
+        let value = match display.handle.get_vcp_feature(BRIGHTNESS) {
+            Ok(value) => value,
+            Err(e) => {
+                eprintln!("skip display {}: {e}", display.info.id);
+                continue;
+            }
+        };
+        displays.push(LightDisplay {
+            id: display.info.id.clone(),
+            model: display.info.model_name.clone(),
+            serial: display
+                .info
+                .serial_number
+                .clone()
+                .or(display.info.serial.map(|s| s.to_string())),
+            brightness: value.value(),
+            max: value.maximum(),
+        });
+        handles.push(display);
     }
+    (displays, Handles(handles))
 }
@@ -1,20 +1,61 @@
 use message::Message;
+use std::os::unix::process::CommandExt;
 use std::{sync::Arc, thread::sleep, time::Duration};
 use xcb::x;
 
 mod alsa;
+mod battery;
 mod bspwm;
+mod command;
 mod components;
+mod cpu;
+mod disk;
 mod error;
+mod i3bar;
+mod ipc;
+mod lemonbar;
 mod light;
+mod memory;
 mod message;
+mod network;
+mod render;
+mod scheduler;
+mod temperature;
+mod uevent;
 mod util;
+mod waybar;
 mod x11;
 
-use components::{BspwmComponent, Component, Date, Event, Light, Painter, Title, Volume, title};
-use x11::{create_window, setup_ewmh};
+use components::memory::MemoryFormat;
+use components::rewrite::TitleRule;
+use components::title::Overflow;
+use components::waybar::WaybarFormat;
+use components::{
+    Battery, BspwmComponent, Component, Cpu, Date, Disk, Event, I3bar, Lemonbar, Light, Memory,
+    Network, Painter, Script, Tail, Taskbar, Temperature, Title, Tray, Volume,
+};
+use x11::{WindowTracker, create_window, setup_ewmh};
+
+// Waybar 格式的 JSON：只显示小时和分钟，放得进右边窄的位置，悬停时显示 uptime -p
+const UPTIME: &str = r#"printf '{"text": "%s", "tooltip": "%s"}\n' \
+    "$(awk '{ printf "%dh %dm", $1 / 3600, $1 % 3600 / 60 }' /proc/uptime)" "$(uptime -p)""#;
 
 fn main() -> error::MyResult<()> {
+    // mybar render：不连接 X，把状态栏画到图片上
+    if std::env::args().nth(1).as_deref() == Some("render") {
+        return render::run(std::env::args().skip(2));
+    }
+    // reload：run 返回时所有组件都已经释放（子进程已结束、socket 已删除），再原样重新执行自己
+    if run()? {
+        let exe = std::env::current_exe()?;
+        let args = std::env::args_os().skip(1);
+        return Err(std::process::Command::new(exe).args(args).exec().into());
+    }
+    Ok(())
+}
+
+/// 返回 true 表示收到了 reload 命令
+fn run() -> error::MyResult<bool> {
     let (conn, screen_num) = xcb::Connection::connect(None)?;
     let conn = Arc::new(conn);
     let setup = conn.get_setup();
@@ -41,21 +82,112 @@ fn main() -> error::MyResult<()> {
     let width = screen.width_in_pixels();
     let height = 40;
     let painter = Painter::new(&conn, window, visual_type, width as i32, height)?;
+    // lemonbar 兼容模式：只显示 stdin 读到的内容，stdout 留给点击区域的命令
+    if std::env::args().any(|arg| arg == "--lemonbar") {
+        return run_lemonbar(&conn, window, &painter, width).map(|_| false);
+    }
     let audio = alsa::Audio::default();
     let volume = Volume::new(&painter, &audio);
-    let light = Light::new(&painter);
-    let date = Date::new(&painter);
-    let title = Title::new(&painter, &ewmh_conn);
+    let mut light = Light::new(&painter)
+        .with_linked(std::env::args().any(|arg| arg == "--light-linked"))
+        .with_tooltips(&conn, window, visual_type);
+    if let Some(name) = arg_value("--light-only") {
+        light = light.with_only(&name);
+    }
+    let light = light.start(&conn, window);
+    let date = Date::new(&painter)
+        .with_format("%a %b %e %T", "%Y-%m-%d %H:%M")?
+        .with_zones(&["UTC"])
+        .with_calendar(&conn, window, visual_type, true);
+    let scheduler = scheduler::Scheduler::new(&conn, window);
+    let tracker = WindowTracker::new(&conn, &ewmh_conn, screen.root())?;
+    // 标题和任务栏共用一个位置，--taskbar 时显示任务栏
+    let windows: Box<dyn Component> = if std::env::args().any(|arg| arg == "--taskbar") {
+        Box::new(
+            Taskbar::new(&painter, &tracker)
+                .with_all_desktops(std::env::args().any(|arg| arg == "--taskbar-all-desktops")),
+        )
+    } else {
+        Box::new(
+            Title::new(&painter, &tracker)
+                .with_marquee(&scheduler)
+                .with_overflow(Overflow::Middle)
+                .with_placeholder("\u{f108} {desktop}")
+                .with_rule(TitleRule::new(r" [—-] Mozilla Firefox$", "")?)
+                .with_rule(TitleRule::class_color("firefox", "#fab387"))
+                .with_rule(
+                    TitleRule::new(r"^\S+@\S+:\s*(?<cwd>.*)$", "${cwd}")?
+                        .for_class("Alacritty")
+                        .with_color("#a6e3a1"),
+                ),
+        )
+    };
+    // 系统信息的位置默认是内置的模块，--i3bar 时换成 i3status 这类生产者的整条状态行
+    let system: Vec<Box<dyn Component>> = match arg_value("--i3bar") {
+        Some(command) => vec![Box::new(
+            I3bar::new(&painter, &command)
+                .with_name("i3bar")
+                .with_position(1040, 675)
+                .start(&conn, window),
+        )],
+        None => system_modules(&painter, &scheduler)?,
+    };
+    // 右边的小位置默认显示 uptime，--tail 时换成一直运行的命令（例如 journalctl -f）的最新一行
+    let custom: Box<dyn Component> = match arg_value("--tail") {
+        Some(command) => {
+            let mut tail = Tail::new(&painter, &command)
+                .with_name("tail")
+                .with_position(1725, 70);
+            if std::env::args().any(|arg| arg == "--tail-json") {
+                tail = tail.with_json(tail_format());
+            }
+            Box::new(
+                tail.with_tooltips(&conn, window, visual_type)
+                    .start(&conn, window),
+            )
+        }
+        // 左键用通知显示 uptime 的完整输出（包括负载）
+        None => Box::new(
+            Script::new(&painter, UPTIME)
+                .with_name("uptime")
+                .with_position(1725, 70)
+                .with_json(WaybarFormat::new())
+                .with_interval(Duration::from_secs(60))
+                .with_timeout(Duration::from_secs(1))
+                .with_click(1, r#"notify-send "$(uptime)""#)
+                .with_tooltips(&conn, window, visual_type)
+                .start(&conn, window),
+        ),
+    };
+    if let Err(e) = uevent::watch(&conn, window, "power_supply", Message::BatteryUpdate) {
+        eprintln!("Error watching power supply uevents: {}", e);
+    }
     let bspwm: Arc<std::sync::Mutex<bspwm::Bspwm>> = bspwm::Bspwm::new(&conn, window);
     let bspwm_component = BspwmComponent::new(&painter, bspwm);
 
-    let components: Vec<Box<dyn Component>> = vec![
+    let mut components: Vec<Box<dyn Component>> = vec![
         Box::new(light),
         Box::new(volume),
         Box::new(date),
-        Box::new(title),
+        windows,
         Box::new(bspwm_component),
+        custom,
     ];
+    components.extend(system);
+    // 已经有别的托盘在运行时不影响状态栏其它部分
+    match Tray::new(&painter, &conn, window, screen_num, &visual_type) {
+        Ok(tray) => components.push(Box::new(tray)),
+        Err(e) => eprintln!("System tray disabled: {}", e),
+    }
+    let ipc = match ipc::Server::listen(&conn, window) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            eprintln!("IPC disabled: {}", e);
+            None
+        }
+    };
+    let mut visible = true;
+    let mut watcher = ipc::Watcher::new();
     let conn_clone = Arc::clone(&conn);
     std::thread::spawn(move || {
         loop {
@@ -65,7 +197,43 @@ fn main() -> error::MyResult<()> {
     });
 
     loop {
-        match conn.wait_for_event()? {
+        let event = match conn.wait_for_event() {
+            Ok(event) => event,
+            // 异步请求的错误（例如操作一个刚被销毁的窗口）不应该让状态栏退出
+            Err(xcb::Error::Protocol(e)) => {
+                eprintln!("X protocol error: {:?}", e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(ipc) = &ipc {
+            watcher.poll(ipc, &components);
+        }
+        // 活动窗口或它的标题变了
+        if tracker.handle_event(&event).is_some() {
+            for title in components.iter().filter(|c| c.name() == "title") {
+                if let Err(e) = title.draw() {
+                    eprintln!("Error drawing title: {}", e);
+                }
+            }
+            conn.flush()?;
+        }
+        // 先交给组件处理它们自己的窗口（例如弹出的日历）
+        let mut handled = false;
+        for component in &components {
+            match component.handle_x_event(&event) {
+                Ok(true) => {
+                    handled = true;
+                    break;
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!("Error handling x event: {}", e),
+            }
+        }
+        if handled {
+            continue;
+        }
+        match event {
             xcb::Event::X(x::Event::Expose(ev)) => {
                 if ev.count() != 0 {
                     continue;
@@ -84,30 +252,46 @@ fn main() -> error::MyResult<()> {
                 let button = ev.detail();
                 for component in &components {
                     if component.contains_point(x, y) {
-                        if let Err(e) = component.handle_event(&Event::MouseClick { x, y, button })
+                        if let Some(ipc) = &ipc
+                            && ipc.wants("click")
                         {
+                            ipc.publish(&ipc::Event::Click {
+                                module: component.name().to_string(),
+                                x,
+                                y,
+                                button,
+                            });
+                        }
+                        let click = Event::MouseClick {
+                            x,
+                            y,
+                            root_x: ev.root_x(),
+                            root_y: ev.root_y(),
+                            button,
+                        };
+                        if let Err(e) = component.handle_event(&click) {
                             eprintln!("Error handling click event: {}", e);
                         }
                         break;
                     }
                 }
             }
-            xcb::Event::X(xcb::x::Event::PropertyNotify(e)) => {
-                println!(
-                    "titlte change, w: {:?}, w2: {:?}",
-                    e.window(),
-                    title::get_current_window(&ewmh_conn)?
-                );
-                if e.atom() == ewmh_conn.atoms._NET_ACTIVE_WINDOW
-                    || e.atom() == ewmh_conn.atoms._NET_WM_NAME
-                {
-                    components[3].draw()?;
+            xcb::Event::X(x::Event::MotionNotify(ev)) => {
+                let event = Event::MouseMove {
+                    x: ev.event_x(),
+                    y: ev.event_y(),
+                };
+                for component in &components {
+                    if let Err(e) = component.handle_event(&event) {
+                        eprintln!("Error handling motion event: {}", e);
+                    }
                 }
-                if e.atom() == ewmh_conn.atoms._NET_ACTIVE_WINDOW
-                    && e.window() == title::get_current_window(&ewmh_conn)?
-                {
-                    println!("window: {:?}", e.window());
-                    //
+            }
+            xcb::Event::X(x::Event::LeaveNotify(_)) => {
+                for component in &components {
+                    if let Err(e) = component.handle_event(&Event::MouseLeave) {
+                        eprintln!("Error handling leave event: {}", e);
+                    }
                 }
             }
             xcb::Event::X(x::Event::KeyPress(ev)) => {
@@ -129,6 +313,48 @@ fn main() -> error::MyResult<()> {
                     message::Message::BspwmUpdate => {
                         components[4].draw()?;
                     }
+                    // 由组件在 handle_x_event 中处理
+                    message::Message::TitleTick
+                    | message::Message::BatteryUpdate
+                    | message::Message::CpuUpdate
+                    | message::Message::MemoryUpdate
+                    | message::Message::NetworkUpdate
+                    | message::Message::TemperatureUpdate
+                    | message::Message::DiskUpdate
+                    | message::Message::ScriptUpdate
+                    | message::Message::TailUpdate
+                    | message::Message::I3barUpdate
+                    | message::Message::LemonbarUpdate
+                    | message::Message::LightUpdate => {}
+                    message::Message::Ipc => {
+                        let Some(ipc) = &ipc else { continue };
+                        let mut requests = ipc.requests();
+                        while let Some((request, mut stream)) = requests.next() {
+                            match request {
+                                ipc::Request::Reload => {
+                                    ipc::reply(&mut stream, Ok(()));
+                                    // 重载后这个 Server 就没了，排在后面的命令不再执行
+                                    for (_, mut stream) in requests {
+                                        ipc::reply(
+                                            &mut stream,
+                                            Err("bar is reloading".to_string()),
+                                        );
+                                    }
+                                    return Ok(true);
+                                }
+                                ipc::Request::State => {
+                                    let state = ipc::Reply::with_data(ipc::dump(&components));
+                                    ipc::reply(&mut stream, state);
+                                    continue;
+                                }
+                                _ => {}
+                            }
+                            let result =
+                                execute(&request, &components, &conn, window, &mut visible);
+                            conn.flush()?;
+                            ipc::reply(&mut stream, result);
+                        }
+                    }
                 }
             }
             _ => {
@@ -137,3 +363,178 @@ fn main() -> error::MyResult<()> {
         }
     }
 }
+
+/// 内置的系统信息模块，按命令行参数配置
+fn system_modules<'a>(
+    painter: &'a Painter<'a>,
+    scheduler: &scheduler::Scheduler,
+) -> error::MyResult<Vec<Box<dyn Component + 'a>>> {
+    let battery = Battery::new(painter, battery::PowerSupply::default()).with_refresh(scheduler);
+    // 每个核心的柱子和历史曲线放不下两个，--cpu-history 时用曲线代替柱子
+    let cpu_history = std::env::args().any(|arg| arg == "--cpu-history");
+    let cpu = Cpu::new(painter, cpu::CpuStat::default())
+        .with_per_core(!cpu_history)
+        .with_history(cpu_history)
+        .with_refresh(scheduler);
+    let mut memory = Memory::new(painter, memory::MemorySource::default())
+        .with_zram(std::env::args().any(|arg| arg == "--memory-zram"));
+    if let Some(format) = arg_value("--memory-format") {
+        memory = memory.with_format(MemoryFormat::parse(&format)?);
+    }
+    let memory = memory.with_refresh(scheduler);
+    let network = Network::new(painter, network::NetSource::default())
+        .with_rates(!std::env::args().any(|arg| arg == "--network-no-rates"))
+        .with_refresh(scheduler);
+    let mut temperature = Temperature::new(painter, temperature::SensorSource::default())
+        .with_fan(std::env::args().any(|arg| arg == "--temperature-fan"));
+    if let Some(label) = arg_value("--temperature-sensor") {
+        temperature = temperature.with_sensor(&label);
+    }
+    if let Some((warning, critical)) = thresholds("--temperature-thresholds")? {
+        temperature = temperature.with_thresholds(warning, critical);
+    }
+    let temperature = temperature.with_refresh(scheduler);
+    let mut disk = Disk::new(painter).with_free(!std::env::args().any(|arg| arg == "--disk-used"));
+    if let Some(mounts) = arg_value("--disk-mounts") {
+        disk = disk.with_mounts(&mounts.split(',').collect::<Vec<_>>());
+    }
+    if let Some((warning, critical)) = thresholds("--disk-thresholds")? {
+        disk = disk.with_thresholds(warning, critical);
+    }
+    let disk = disk.with_refresh(scheduler);
+    Ok(vec![
+        Box::new(battery),
+        Box::new(cpu),
+        Box::new(memory),
+        Box::new(network),
+        Box::new(temperature),
+        Box::new(disk),
+    ])
+}
+
+/// 命令行里 `--name value` 形式的参数
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    args.next()?;
+    args.next()
+}
+
+/// --tail-json 时的格式，按 Waybar 播放器脚本的惯例：alt 是播放状态，percentage 是播放进度，
+/// --tail-bar 时进度再画成进度条
+fn tail_format() -> WaybarFormat {
+    WaybarFormat::new()
+        .with_icon("playing", "\u{f04b}")
+        .with_icon("paused", "\u{f04c}")
+        .with_ramp(&["\u{2581}", "\u{2583}", "\u{2585}", "\u{2587}"])
+        .with_bar(std::env::args().any(|arg| arg == "--tail-bar"))
+}
+
+/// "warning,critical" 形式的阈值参数
+fn thresholds(name: &str) -> error::MyResult<Option<(f64, f64)>> {
+    let Some(value) = arg_value(name) else {
+        return Ok(None);
+    };
+    let parsed = value
+        .split_once(',')
+        .and_then(|(warning, critical)| Some((warning.parse().ok()?, critical.parse().ok()?)));
+    match parsed {
+        Some(thresholds) => Ok(Some(thresholds)),
+        None => Err(error::MyBarError::Other(format!(
+            "{name} expects warning,critical, got {value:?}"
+        ))),
+    }
+}
+
+/// 执行 mybar-msg 发来的 hide/show/toggle/update/action 命令
+fn execute(
+    request: &ipc::Request,
+    components: &[Box<dyn Component + '_>],
+    conn: &xcb::Connection,
+    window: x::Window,
+    visible: &mut bool,
+) -> Result<(), String> {
+    let show = match request {
+        ipc::Request::Hide => false,
+        ipc::Request::Show => true,
+        ipc::Request::Toggle => !*visible,
+        ipc::Request::Update(module) | ipc::Request::Action(module, _) => {
+            let mut found = false;
+            for component in components.iter().filter(|c| c.name() == module) {
+                found = true;
+                let result = match request {
+                    ipc::Request::Action(_, name) => component.action(name),
+                    _ => component.update(),
+                };
+                result.map_err(|e| e.to_string())?;
+            }
+            return if found {
+                Ok(())
+            } else {
+                Err(format!("no module named {module}"))
+            };
+        }
+        // 由主循环或 Server 的后台线程处理
+        ipc::Request::Reload | ipc::Request::Subscribe(_) | ipc::Request::State => return Ok(()),
+    };
+    if show != *visible {
+        if show {
+            conn.send_request(&x::MapWindow { window });
+        } else {
+            conn.send_request(&x::UnmapWindow { window });
+            for component in components {
+                component.close_popups();
+            }
+        }
+        *visible = show;
+    }
+    Ok(())
+}
+
+/// 只有一个 Lemonbar 组件的事件循环，不往 stdout 打印任何调试信息
+fn run_lemonbar(
+    conn: &Arc<xcb::Connection>,
+    window: x::Window,
+    painter: &Painter,
+    width: u16,
+) -> error::MyResult<()> {
+    let lemonbar = Lemonbar::new(painter)
+        .with_position(0, width)
+        .start(conn, window);
+    loop {
+        let event = match conn.wait_for_event() {
+            Ok(event) => event,
+            Err(xcb::Error::Protocol(e)) => {
+                eprintln!("X protocol error: {:?}", e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        // 和主循环一样，绘制出错只打印，不让状态栏退出
+        match lemonbar.handle_x_event(&event) {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => eprintln!("Error handling x event: {}", e),
+        }
+        match event {
+            xcb::Event::X(x::Event::Expose(ev)) if ev.count() == 0 => {
+                if let Err(e) = lemonbar.draw() {
+                    eprintln!("Error drawing lemonbar: {}", e);
+                }
+                conn.flush()?;
+            }
+            xcb::Event::X(x::Event::ButtonPress(ev)) => {
+                let event = Event::MouseClick {
+                    x: ev.event_x(),
+                    y: ev.event_y(),
+                    root_x: ev.root_x(),
+                    root_y: ev.root_y(),
+                    button: ev.detail(),
+                };
+                if let Err(e) = lemonbar.handle_event(&event) {
+                    eprintln!("Error handling click event: {}", e);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::{MyBarError, MyResult};
+
+/// /proc/meminfo 中用到的字段，单位 KiB
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemInfo {
+    pub total: u64,
+    pub available: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+impl MemInfo {
+    /// 已用内存：MemTotal − MemAvailable，不把可回收的缓存算作已用
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+
+    pub fn used_ratio(&self) -> f64 {
+        ratio(self.used(), self.total)
+    }
+
+    pub fn swap_ratio(&self) -> f64 {
+        ratio(self.swap_used(), self.swap_total)
+    }
+}
+
+fn ratio(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    used as f64 / total as f64
+}
+
+/// 所有 zram 设备的压缩统计，单位字节
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ZramStats {
+    /// 压缩前的数据大小
+    pub original: u64,
+    /// 压缩后实际占用的内存
+    pub used: u64,
+}
+
+impl ZramStats {
+    /// 压缩率，例如 3.0 表示压缩到原来的三分之一
+    pub fn ratio(&self) -> f64 {
+        if self.used == 0 {
+            return 0.0;
+        }
+        self.original as f64 / self.used as f64
+    }
+}
+
+fn parse_meminfo(text: &str) -> MyResult<MemInfo> {
+    let mut info = MemInfo::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value: u64 = value
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .unwrap_or(0);
+        match key {
+            "MemTotal" => info.total = value,
+            "MemAvailable" => info.available = value,
+            "SwapTotal" => info.swap_total = value,
+            "SwapFree" => info.swap_free = value,
+            _ => {}
+        }
+    }
+    if info.total == 0 {
+        return Err(MyBarError::Other("MemTotal missing in meminfo".to_string()));
+    }
+    Ok(info)
+}
+
+/// 内存信息的来源，测试时可以把 /proc 和 /sys 指向假的目录
+pub struct MemorySource {
+    proc_root: PathBuf,
+    sys_root: PathBuf,
+}
+
+impl Default for MemorySource {
+    fn default() -> Self {
+        Self::new("/proc", "/sys")
+    }
+}
+
+impl MemorySource {
+    pub fn new(proc_root: impl Into<PathBuf>, sys_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            sys_root: sys_root.into(),
+        }
+    }
+
+    pub fn meminfo(&self) -> MyResult<MemInfo> {
+        parse_meminfo(&fs::read_to_string(self.proc_root.join("meminfo"))?)
+    }
+
+    /// 汇总 /sys/block/zram*/mm_stat，没有 zram 设备时返回 None
+    pub fn zram(&self) -> MyResult<Option<ZramStats>> {
+        let mut stats: Option<ZramStats> = None;
+        for entry in fs::read_dir(self.sys_root.join("block"))? {
+            let path = entry?.path();
+            let is_zram = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("zram"));
+            if !is_zram {
+                continue;
+            }
+            // orig_data_size compr_data_size mem_used_total ...
+            let Ok(text) = fs::read_to_string(path.join("mm_stat")) else {
+                continue;
+            };
+            let fields: Vec<u64> = text
+                .split_whitespace()
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            if let (Some(original), Some(used)) = (fields.first(), fields.get(2)) {
+                let total = stats.get_or_insert_default();
+                total.original += original;
+                total.used += used;
+            }
+        }
+        Ok(stats)
+    }
+}
+
+/// 把 KiB 转成 GiB
+pub fn kib_to_gib(kib: u64) -> f64 {
+    kib as f64 / 1024.0 / 1024.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_meminfo;
+
+    #[test]
+    fn used_excludes_cache() {
+        let info = parse_meminfo(
+            "MemTotal:       16000000 kB\n\
+             MemFree:         1000000 kB\n\
+             MemAvailable:   12000000 kB\n\
+             Cached:          9000000 kB\n\
+             SwapTotal:       4000000 kB\n\
+             SwapFree:        3000000 kB\n",
+        )
+        .unwrap();
+        assert_eq!(info.used(), 4000000);
+        assert_eq!(info.used_ratio(), 0.25);
+        assert_eq!(info.swap_used(), 1000000);
+        assert_eq!(info.swap_ratio(), 0.25);
+        assert!(parse_meminfo("").is_err());
+    }
+}
@@ -1,8 +1,22 @@
 use crate::error;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Message {
-    Date,
-    BspwmUpdate,
+    Date = 0,
+    BspwmUpdate = 1,
+    TitleTick = 2,
+    BatteryUpdate = 3,
+    CpuUpdate = 4,
+    MemoryUpdate = 5,
+    NetworkUpdate = 6,
+    TemperatureUpdate = 7,
+    DiskUpdate = 8,
+    ScriptUpdate = 9,
+    TailUpdate = 10,
+    I3barUpdate = 11,
+    LemonbarUpdate = 12,
+    Ipc = 13,
+    LightUpdate = 14,
 }
 
 impl From<xcb::x::ClientMessageData> for Message {
@@ -12,6 +26,19 @@ impl From<xcb::x::ClientMessageData> for Message {
                 let v = data[0];
                 match v {
                     1 => Message::BspwmUpdate,
+                    2 => Message::TitleTick,
+                    3 => Message::BatteryUpdate,
+                    4 => Message::CpuUpdate,
+                    5 => Message::MemoryUpdate,
+                    6 => Message::NetworkUpdate,
+                    7 => Message::TemperatureUpdate,
+                    8 => Message::DiskUpdate,
+                    9 => Message::ScriptUpdate,
+                    10 => Message::TailUpdate,
+                    11 => Message::I3barUpdate,
+                    12 => Message::LemonbarUpdate,
+                    13 => Message::Ipc,
+                    14 => Message::LightUpdate,
                     _ => Message::Date,
                 }
             }
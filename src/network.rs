@@ -0,0 +1,276 @@
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::error::MyResult;
+
+// /proc/net/route 中的 RTF_UP 标志
+const RTF_UP: u32 = 0x1;
+// /sys/class/net/<if>/type 为 ARPHRD_NONE 的一般是 tun 或 wireguard
+const ARPHRD_NONE: u32 = 65534;
+// /proc/net/wireless 中链路质量的满分
+const MAX_QUALITY: f64 = 70.0;
+
+/// 默认路由所在接口的类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NetKind {
+    Wired,
+    Wireless,
+    Vpn,
+    Disconnected,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetStatus {
+    pub interface: Option<String>,
+    pub kind: NetKind,
+    pub up: bool,
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+    /// 每秒收发的字节数
+    pub rx_rate: f64,
+    pub tx_rate: f64,
+    /// 无线链路质量 0 - 100
+    pub quality: Option<u8>,
+}
+
+impl NetStatus {
+    fn disconnected() -> Self {
+        Self {
+            interface: None,
+            kind: NetKind::Disconnected,
+            up: false,
+            ipv4: None,
+            ipv6: None,
+            rx_rate: 0.0,
+            tx_rate: 0.0,
+            quality: None,
+        }
+    }
+}
+
+/// 从 /proc/net/route 中找默认路由的接口，有多条时取 metric 最小的
+fn parse_default_route(text: &str) -> Option<String> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+            let metric: u32 = fields.get(6)?.parse().ok()?;
+            (fields[1] == "00000000" && flags & RTF_UP != 0).then(|| (metric, fields[0]))
+        })
+        .min_by_key(|(metric, _)| *metric)
+        .map(|(_, iface)| iface.to_string())
+}
+
+/// 从 /proc/net/dev 中读取接口累计收发的字节数
+fn parse_dev(text: &str, interface: &str) -> Option<(u64, u64)> {
+    text.lines().skip(2).find_map(|line| {
+        let (name, counters) = line.split_once(':')?;
+        if name.trim() != interface {
+            return None;
+        }
+        let fields: Vec<u64> = counters
+            .split_whitespace()
+            .filter_map(|v| v.parse().ok())
+            .collect();
+        Some((*fields.first()?, *fields.get(8)?))
+    })
+}
+
+/// 从 /proc/net/wireless 中读取链路质量，换算成百分比
+fn parse_wireless(text: &str, interface: &str) -> Option<u8> {
+    text.lines().skip(2).find_map(|line| {
+        let (name, values) = line.split_once(':')?;
+        if name.trim() != interface {
+            return None;
+        }
+        let quality: f64 = values
+            .split_whitespace()
+            .nth(1)?
+            .trim_end_matches('.')
+            .parse()
+            .ok()?;
+        Some((quality * 100.0 / MAX_QUALITY).clamp(0.0, 100.0) as u8)
+    })
+}
+
+/// 接口上的第一个 IPv4 地址和第一个非链路本地的 IPv6 地址
+fn addresses(interface: &str) -> (Option<Ipv4Addr>, Option<Ipv6Addr>) {
+    let mut ipv4 = None;
+    let mut ipv6 = None;
+    let mut ifaddrs: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut ifaddrs) } != 0 {
+        return (None, None);
+    }
+    let mut cursor = ifaddrs;
+    while !cursor.is_null() {
+        let entry = unsafe { &*cursor };
+        cursor = entry.ifa_next;
+        if entry.ifa_addr.is_null() {
+            continue;
+        }
+        let name = unsafe { std::ffi::CStr::from_ptr(entry.ifa_name) };
+        if name.to_bytes() != interface.as_bytes() {
+            continue;
+        }
+        match unsafe { (*entry.ifa_addr).sa_family } as i32 {
+            libc::AF_INET if ipv4.is_none() => {
+                let addr = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in) };
+                ipv4 = Some(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)));
+            }
+            libc::AF_INET6 if ipv6.is_none() => {
+                let addr = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in6) };
+                let addr = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+                if !addr.is_unicast_link_local() {
+                    ipv6 = Some(addr);
+                }
+            }
+            _ => {}
+        }
+    }
+    unsafe { libc::freeifaddrs(ifaddrs) };
+    (ipv4, ipv6)
+}
+
+/// 网络状态的来源，测试时可以把 /proc 和 /sys 指向假的目录
+///
+/// 速率由相邻两次读取 /proc/net/dev 的差值算出，所以需要保存上一次的计数。
+pub struct NetSource {
+    proc_root: PathBuf,
+    sys_root: PathBuf,
+    // 上一次读取的接口名、收发字节数和时间
+    previous: Option<(String, u64, u64, Instant)>,
+}
+
+impl Default for NetSource {
+    fn default() -> Self {
+        Self::new("/proc", "/sys")
+    }
+}
+
+impl NetSource {
+    pub fn new(proc_root: impl Into<PathBuf>, sys_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            sys_root: sys_root.into(),
+            previous: None,
+        }
+    }
+
+    fn kind(&self, interface: &str) -> NetKind {
+        let dir = self.sys_root.join("class/net").join(interface);
+        let arp_type: Option<u32> = fs::read_to_string(dir.join("type"))
+            .ok()
+            .and_then(|t| t.trim().parse().ok());
+        let vpn_name = ["tun", "tap", "wg"]
+            .iter()
+            .any(|prefix| interface.starts_with(prefix));
+        if vpn_name || arp_type == Some(ARPHRD_NONE) {
+            NetKind::Vpn
+        } else if dir.join("wireless").exists() || dir.join("phy80211").exists() {
+            NetKind::Wireless
+        } else {
+            NetKind::Wired
+        }
+    }
+
+    fn operstate(&self, interface: &str) -> bool {
+        let path = self
+            .sys_root
+            .join("class/net")
+            .join(interface)
+            .join("operstate");
+        // tun 之类的设备没有载波概念，连通时报告 unknown
+        matches!(
+            fs::read_to_string(path).as_deref().map(str::trim),
+            Ok("up") | Ok("unknown")
+        )
+    }
+
+    pub fn status(&mut self) -> MyResult<NetStatus> {
+        let route = fs::read_to_string(self.proc_root.join("net/route"))?;
+        let Some(interface) = parse_default_route(&route) else {
+            self.previous = None;
+            return Ok(NetStatus::disconnected());
+        };
+        let up = self.operstate(&interface);
+        let kind = if up {
+            self.kind(&interface)
+        } else {
+            NetKind::Disconnected
+        };
+
+        let dev = fs::read_to_string(self.proc_root.join("net/dev"))?;
+        let now = Instant::now();
+        let (mut rx_rate, mut tx_rate) = (0.0, 0.0);
+        if let Some((rx, tx)) = parse_dev(&dev, &interface) {
+            if let Some((name, prev_rx, prev_tx, then)) = &self.previous {
+                let secs = now.duration_since(*then).as_secs_f64();
+                // 默认路由换了接口时没有可比较的数据
+                if *name == interface && secs > 0.0 {
+                    rx_rate = rx.saturating_sub(*prev_rx) as f64 / secs;
+                    tx_rate = tx.saturating_sub(*prev_tx) as f64 / secs;
+                }
+            }
+            self.previous = Some((interface.clone(), rx, tx, now));
+        }
+
+        let quality = match kind {
+            NetKind::Wireless => fs::read_to_string(self.proc_root.join("net/wireless"))
+                .ok()
+                .and_then(|text| parse_wireless(&text, &interface)),
+            _ => None,
+        };
+        let (ipv4, ipv6) = addresses(&interface);
+        Ok(NetStatus {
+            interface: Some(interface),
+            kind,
+            up,
+            ipv4,
+            ipv6,
+            rx_rate,
+            tx_rate,
+            quality,
+        })
+    }
+}
+
+/// 把每秒字节数格式化成 "512B"、"1.2K"、"3.4M"
+pub fn format_rate(bytes: f64) -> String {
+    if bytes < 1024.0 {
+        format!("{:.0}B", bytes)
+    } else if bytes < 1024.0 * 1024.0 {
+        format!("{:.1}K", bytes / 1024.0)
+    } else {
+        format!("{:.1}M", bytes / 1024.0 / 1024.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_rate, parse_default_route, parse_dev, parse_wireless};
+
+    #[test]
+    fn parse_proc_net() {
+        let route = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+                     wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0\n\
+                     eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+                     eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n";
+        assert_eq!(parse_default_route(route).as_deref(), Some("eth0"));
+
+        let dev = "Inter-|   Receive                                                |  Transmit\n \
+                   face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n    \
+                   lo: 1000 10 0 0 0 0 0 0 1000 10 0 0 0 0 0 0\n  \
+                   eth0: 123456 100 0 0 0 0 0 0 654321 90 0 0 0 0 0 0\n";
+        assert_eq!(parse_dev(dev, "eth0"), Some((123456, 654321)));
+
+        let wireless = "Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE\n \
+                        face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22\n \
+                        wlan0: 0000   56.  -54.  -256        0      0      0      0     10        0\n";
+        assert_eq!(parse_wireless(wireless, "wlan0"), Some(80));
+
+        assert_eq!(format_rate(2048.0), "2.0K");
+    }
+}
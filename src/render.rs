@@ -0,0 +1,182 @@
+// mybar render：不连接 X，用固定数据把状态栏画到 PNG，用来给文档截图和检查主题改动
+use std::fs::File;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+use chrono::TimeZone;
+
+use crate::alsa::FixedMixer;
+use crate::battery::PowerSupply;
+use crate::bspwm::Bspwm;
+use crate::components::{
+    Battery, BspwmComponent, Component, Cpu, Date, Disk, Memory, Network, Painter, Temperature,
+    Volume,
+};
+use crate::cpu::{CpuStat, CpuUsage};
+use crate::disk::DiskUsage;
+use crate::error::{MyBarError, MyResult};
+use crate::memory::MemorySource;
+use crate::network::{NetKind, NetStatus};
+use crate::temperature::SensorSource;
+
+const HEIGHT: i32 = 40;
+const USAGE: &str = "usage: mybar render --fixtures <dir> [--output bar.png] [--width 1920]";
+// 一台显示器，聚焦在 1 号桌面，3 号桌面有紧急窗口
+const BSPWM_REPORT: &str = "WMeDP-1:O1:o2:u3:f4:f5:LT:TT:G";
+const GIB: u64 = 1024 * 1024 * 1024;
+
+pub struct Options {
+    pub output: PathBuf,
+    pub width: i32,
+    /// 假的 /proc 和 /sys，例如仓库里的 fixtures 目录
+    pub fixtures: PathBuf,
+}
+
+impl Options {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut output = PathBuf::from("bar.png");
+        let mut width = 1920;
+        let mut fixtures = None;
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--output" => output = value()?.into(),
+                "--width" => {
+                    width = value()?
+                        .parse()
+                        .map_err(|_| "--width must be a number".to_string())?
+                }
+                "--fixtures" => fixtures = Some(value()?.into()),
+                _ => return Err(format!("unknown argument {arg}")),
+            }
+        }
+        Ok(Self {
+            output,
+            width,
+            fixtures: fixtures.ok_or("--fixtures is required")?,
+        })
+    }
+}
+
+pub fn run(args: impl Iterator<Item = String>) -> MyResult<()> {
+    let options = match Options::parse(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let surface = render(options.width, &options.fixtures)?;
+    let mut file = File::create(&options.output)?;
+    surface
+        .write_to_png(&mut file)
+        .map_err(|e| MyBarError::Other(format!("write {}: {e}", options.output.display())))?;
+    Ok(())
+}
+
+/// 把不依赖 X 的组件按默认位置画到一张 width x 40 的透明图片上，
+/// 组件的位置是固定的，width 放不下最右边的组件时返回错误
+pub fn render(width: i32, fixtures: &Path) -> MyResult<cairo::ImageSurface> {
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, HEIGHT)?;
+    // painter 持有 surface 的引用，写文件之前要先释放
+    {
+        let painter = Painter::for_surface(&surface, width, HEIGHT)?;
+        let mixer = FixedMixer::new(0.6, true);
+        let time = chrono::FixedOffset::east_opt(8 * 3600)
+            .and_then(|offset| offset.with_ymd_and_hms(2025, 3, 1, 12, 30, 0).single())
+            .ok_or_else(|| MyBarError::Other("invalid fixture time".to_string()))?;
+        let sys = fixtures.join("sys");
+        let components: Vec<Box<dyn Component>> = vec![
+            Box::new(BspwmComponent::new(
+                &painter,
+                Bspwm::from_report(BSPWM_REPORT),
+            )),
+            Box::new(Memory::new(
+                &painter,
+                MemorySource::new(fixtures.join("proc"), &sys),
+            )),
+            Box::new(Date::new(&painter).with_time(time)),
+            Box::new(Temperature::new(&painter, SensorSource::new(&sys))),
+            Box::new(Battery::new(
+                &painter,
+                PowerSupply::new(sys.join("class/power_supply")),
+            )),
+            Box::new(Volume::new(&painter, &mixer)),
+            Box::new(
+                Cpu::new(&painter, CpuStat::default())
+                    .with_per_core(true)
+                    .with_usage(CpuUsage {
+                        total: 0.23,
+                        cores: vec![0.4, 0.1, 0.3, 0.1],
+                    }),
+            ),
+            Box::new(Disk::new(&painter).with_usage(
+                "/",
+                DiskUsage {
+                    total: 256 * GIB,
+                    available: 94 * GIB,
+                    free: 107 * GIB,
+                },
+            )),
+            Box::new(
+                Network::new(&painter, Default::default()).with_status(NetStatus {
+                    interface: Some("wlan0".to_string()),
+                    kind: NetKind::Wireless,
+                    up: true,
+                    ipv4: Some(Ipv4Addr::new(192, 168, 1, 23)),
+                    ipv6: None,
+                    rx_rate: 1.2 * 1024.0 * 1024.0,
+                    tx_rate: 48.0 * 1024.0,
+                    quality: Some(72),
+                }),
+            ),
+        ];
+        let right = components
+            .iter()
+            .map(|c| {
+                let (x, _, w, _) = c.get_bounds();
+                x as i32 + w as i32
+            })
+            .max()
+            .unwrap_or(0);
+        if width < right {
+            return Err(MyBarError::Other(format!(
+                "--width {width} is too narrow, the layout needs at least {right}"
+            )));
+        }
+        for component in &components {
+            component.draw()?;
+        }
+        painter.flush()?;
+    }
+    Ok(surface)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{Options, render};
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+    #[test]
+    fn parse_options() {
+        let args = ["--width", "800", "--output", "out.png", "--fixtures", "f"];
+        let options = Options::parse(args.map(String::from).into_iter()).unwrap();
+        assert_eq!(options.width, 800);
+        assert_eq!(options.output.to_str(), Some("out.png"));
+        assert_eq!(options.fixtures.to_str(), Some("f"));
+
+        assert!(Options::parse(["--width", "800"].map(String::from).into_iter()).is_err());
+        assert!(Options::parse(["--width"].map(String::from).into_iter()).is_err());
+        assert!(Options::parse(["--width", "wide"].map(String::from).into_iter()).is_err());
+    }
+
+    #[test]
+    fn reject_narrow_width() {
+        assert!(render(800, Path::new(FIXTURES)).is_err());
+        let surface = render(1920, Path::new(FIXTURES)).unwrap();
+        assert_eq!(surface.width(), 1920);
+    }
+}
@@ -0,0 +1,34 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::message::Message;
+
+/// 定时向状态栏窗口发送消息，由主循环在 X 线程上处理
+///
+/// 所有定时任务共用 timer 的一个线程，不必每个组件各开一个线程。
+pub struct Scheduler {
+    timer: timer::Timer,
+    conn: Arc<xcb::Connection>,
+    window: xcb::x::Window,
+}
+
+impl Scheduler {
+    pub fn new(conn: &Arc<xcb::Connection>, window: xcb::x::Window) -> Self {
+        Self {
+            timer: timer::Timer::new(),
+            conn: Arc::clone(conn),
+            window,
+        }
+    }
+
+    /// 每隔 interval 发送一次 message，返回的 Guard 被 drop 时停止
+    pub fn every(&self, interval: Duration, message: Message) -> timer::Guard {
+        let conn = Arc::clone(&self.conn);
+        let window = self.window;
+        let interval = chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::MAX);
+        self.timer.schedule_repeating(interval, move || {
+            if let Err(e) = message.send(&conn, window) {
+                eprintln!("scheduler: send message: {e}");
+            }
+        })
+    }
+}
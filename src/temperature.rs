@@ -0,0 +1,205 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::MyResult;
+
+/// 一个温度传感器的读数
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensor {
+    /// hwmon 的 temp*_label（没有时用 "芯片名 temp*"），或 thermal zone 的 type
+    pub label: String,
+    /// hwmon 芯片名，例如 "coretemp"、"k10temp"；thermal zone 为 None
+    pub chip: Option<String>,
+    pub celsius: f64,
+    /// 硬件报告的临界温度
+    pub critical: Option<f64>,
+}
+
+/// 一个风扇的转速
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fan {
+    pub label: String,
+    pub rpm: u32,
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+// sysfs 中温度的单位是千分之一摄氏度
+fn read_millidegrees(path: &Path) -> Option<f64> {
+    read_trimmed(path)?
+        .parse::<i64>()
+        .ok()
+        .map(|v| v as f64 / 1000.0)
+}
+
+// 按文件名中的数字排序，hwmon10 排在 hwmon2 之后
+fn sorted_entries(dir: &Path, prefix: &str) -> Vec<(u32, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut entries: Vec<(u32, PathBuf)> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let index = name.strip_prefix(prefix)?.parse().ok()?;
+            Some((index, path))
+        })
+        .collect();
+    entries.sort();
+    entries
+}
+
+// hwmon 目录下 temp1_input、fan2_input 这类文件的序号
+fn input_indices(dir: &Path, kind: &str) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut indices: Vec<u32> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            name.strip_prefix(kind)?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    indices.sort();
+    indices
+}
+
+/// /sys/class 下的 hwmon 和 thermal 传感器，测试时可以把根目录指向假的目录
+pub struct SensorSource {
+    sys_root: PathBuf,
+}
+
+impl Default for SensorSource {
+    fn default() -> Self {
+        Self::new("/sys")
+    }
+}
+
+impl SensorSource {
+    pub fn new(sys_root: impl Into<PathBuf>) -> Self {
+        Self {
+            sys_root: sys_root.into(),
+        }
+    }
+
+    fn hwmon_dirs(&self) -> Vec<(String, PathBuf)> {
+        sorted_entries(&self.sys_root.join("class/hwmon"), "hwmon")
+            .into_iter()
+            .map(|(_, dir)| {
+                let chip = read_trimmed(&dir.join("name")).unwrap_or_default();
+                (chip, dir)
+            })
+            .collect()
+    }
+
+    /// 所有温度传感器，hwmon 在前，thermal zone 在后
+    pub fn sensors(&self) -> MyResult<Vec<Sensor>> {
+        let mut sensors = vec![];
+        for (chip, dir) in self.hwmon_dirs() {
+            for i in input_indices(&dir, "temp") {
+                let Some(celsius) = read_millidegrees(&dir.join(format!("temp{i}_input"))) else {
+                    continue;
+                };
+                let label = read_trimmed(&dir.join(format!("temp{i}_label")))
+                    .unwrap_or_else(|| format!("{chip} temp{i}"));
+                sensors.push(Sensor {
+                    label,
+                    chip: Some(chip.clone()),
+                    celsius,
+                    critical: read_millidegrees(&dir.join(format!("temp{i}_crit"))),
+                });
+            }
+        }
+        for (_, dir) in sorted_entries(&self.sys_root.join("class/thermal"), "thermal_zone") {
+            let Some(celsius) = read_millidegrees(&dir.join("temp")) else {
+                continue;
+            };
+            sensors.push(Sensor {
+                label: read_trimmed(&dir.join("type")).unwrap_or_default(),
+                chip: None,
+                celsius,
+                critical: None,
+            });
+        }
+        Ok(sensors)
+    }
+
+    /// 按标签或芯片名选择传感器，不指定时取第一个
+    pub fn sensor(&self, label: Option<&str>) -> MyResult<Option<Sensor>> {
+        let sensors = self.sensors()?;
+        let found = match label {
+            Some(label) => sensors
+                .into_iter()
+                .find(|s| s.label == label || s.chip.as_deref() == Some(label)),
+            None => sensors.into_iter().next(),
+        };
+        Ok(found)
+    }
+
+    /// 所有 hwmon 风扇的转速
+    pub fn fans(&self) -> Vec<Fan> {
+        let mut fans = vec![];
+        for (chip, dir) in self.hwmon_dirs() {
+            for i in input_indices(&dir, "fan") {
+                let Some(rpm) =
+                    read_trimmed(&dir.join(format!("fan{i}_input"))).and_then(|v| v.parse().ok())
+                else {
+                    continue;
+                };
+                let label = read_trimmed(&dir.join(format!("fan{i}_label")))
+                    .unwrap_or_else(|| format!("{chip} fan{i}"));
+                fans.push(Fan { label, rpm });
+            }
+        }
+        fans
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::SensorSource;
+
+    #[test]
+    fn select_sensor_by_label() {
+        let root = std::env::temp_dir().join(format!("mybar-temp-{}", std::process::id()));
+        let hwmon = root.join("class/hwmon/hwmon1");
+        let zone = root.join("class/thermal/thermal_zone0");
+        fs::create_dir_all(&hwmon).unwrap();
+        fs::create_dir_all(&zone).unwrap();
+        for (name, value) in [
+            ("name", "coretemp"),
+            ("temp1_input", "52000"),
+            ("temp1_label", "Package id 0"),
+            ("temp1_crit", "100000"),
+            ("temp2_input", "48000"),
+            ("temp2_label", "Core 0"),
+            ("fan1_input", "1200"),
+        ] {
+            fs::write(hwmon.join(name), format!("{value}\n")).unwrap();
+        }
+        fs::write(zone.join("type"), "acpitz\n").unwrap();
+        fs::write(zone.join("temp"), "40500\n").unwrap();
+
+        let source = SensorSource::new(&root);
+        let package = source.sensor(Some("Package id 0")).unwrap().unwrap();
+        let acpi = source.sensor(Some("acpitz")).unwrap().unwrap();
+        let first = source.sensor(None).unwrap().unwrap();
+        let fans = source.fans();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(package.celsius, 52.0);
+        assert_eq!(package.critical, Some(100.0));
+        assert_eq!(acpi.celsius, 40.5);
+        assert_eq!(first.label, "Package id 0");
+        assert_eq!(fans.len(), 1);
+        assert_eq!(fans[0].label, "coretemp fan1");
+        assert_eq!(fans[0].rpm, 1200);
+    }
+}
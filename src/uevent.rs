@@ -0,0 +1,74 @@
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::Arc;
+
+use crate::error::{MyBarError, MyResult};
+use crate::message::Message;
+
+/// 监听内核 uevent，子系统为 subsystem 的设备发生变化时向状态栏窗口发送 message
+///
+/// 例如插拔电源时 power_supply 子系统会立即发出 change 事件，不必等下一次定时刷新。
+pub fn watch(
+    conn: &Arc<xcb::Connection>,
+    window: xcb::x::Window,
+    subsystem: &str,
+    message: Message,
+) -> MyResult<()> {
+    let mut socket = open_socket()?;
+    let conn = Arc::clone(conn);
+    let key = format!("SUBSYSTEM={subsystem}");
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = match socket.read(&mut buf) {
+                Ok(n) => n,
+                Err(e) => {
+                    eprintln!("read uevent: {e}");
+                    break;
+                }
+            };
+            // 消息是以 \0 分隔的 KEY=VALUE 列表，第一段是 "action@devpath"
+            let matched = buf[..n]
+                .split(|b| *b == 0)
+                .any(|field| field == key.as_bytes());
+            if matched && let Err(e) = message.send(&conn, window) {
+                eprintln!("uevent: send message: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+fn open_socket() -> MyResult<File> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_KOBJECT_UEVENT,
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // 组 1 是内核直接广播的事件
+    let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    addr.nl_groups = 1;
+    let ret = unsafe {
+        libc::bind(
+            fd.as_raw_fd(),
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(MyBarError::Other(format!(
+            "bind uevent socket: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(File::from(fd))
+}
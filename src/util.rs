@@ -44,3 +44,53 @@ pub fn hex_to_argb(color: &str) -> Result<(f64, f64, f64, f64), String> {
 
     Ok((a, r, g, b))
 }
+
+/// 把文字截短到 max 宽度以内，中间或末尾用 "…" 代替，measure 返回文字的绘制宽度
+pub fn ellipsize(text: &str, max: f64, middle: bool, measure: impl Fn(&str) -> f64) -> String {
+    if measure(text) <= max {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let build = |keep: usize| -> String {
+        if middle {
+            let tail = keep / 2;
+            let head = keep - tail;
+            let mut s: String = chars[..head].iter().collect();
+            s.push('…');
+            s.extend(&chars[chars.len() - tail..]);
+            s
+        } else {
+            let mut s: String = chars[..keep].iter().collect();
+            s.push('…');
+            s
+        }
+    };
+    // 二分查找能放下的最多字符数
+    let (mut lo, mut hi) = (0, chars.len());
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        if measure(&build(mid)) <= max {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    build(lo)
+}
+
+#[cfg(test)]
+mod test {
+    use super::ellipsize;
+
+    fn width(s: &str) -> f64 {
+        s.chars().count() as f64
+    }
+
+    #[test]
+    fn ellipsize_end_and_middle() {
+        assert_eq!(ellipsize("short", 10.0, false, width), "short");
+        assert_eq!(ellipsize("abcdefghij", 5.0, false, width), "abcd…");
+        assert_eq!(ellipsize("abcdefghij", 5.0, true, width), "ab…ij");
+        assert_eq!(ellipsize("终端 — Alacritty", 6.0, false, width), "终端 — …");
+    }
+}
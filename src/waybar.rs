@@ -0,0 +1,86 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{MyBarError, MyResult};
+
+/// Waybar 自定义模块（`return-type: json`）输出的一个对象
+///
+/// 所有字段都可以省略，这样为 Waybar 写的脚本不用修改就能使用。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WaybarOutput {
+    pub text: String,
+    /// 用来从图标表里挑图标，例如 "muted"
+    pub alt: Option<String>,
+    pub tooltip: Option<String>,
+    /// Waybar 里 class 可以是字符串也可以是字符串数组
+    #[serde(deserialize_with = "classes")]
+    pub class: Vec<String>,
+    /// 0 到 100
+    pub percentage: Option<f64>,
+}
+
+fn classes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Class {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Class::deserialize(deserializer)? {
+        Class::One(class) if class.is_empty() => vec![],
+        Class::One(class) => vec![class],
+        Class::Many(classes) => classes,
+    })
+}
+
+impl WaybarOutput {
+    /// 普通文本输出
+    pub fn plain(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn parse(output: &str) -> MyResult<Self> {
+        serde_json::from_str(output.trim()).map_err(|e| MyBarError::Other(e.to_string()))
+    }
+}
+
+/// 和 Waybar 的 format-icons 一样按 percentage 在图标里等分选择
+pub fn ramp<T>(icons: &[T], percentage: f64) -> Option<&T> {
+    if icons.is_empty() {
+        return None;
+    }
+    let i = (percentage.clamp(0.0, 100.0) / 100.0 * icons.len() as f64) as usize;
+    icons.get(i.min(icons.len() - 1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{WaybarOutput, ramp};
+
+    #[test]
+    fn parse_waybar_json() {
+        let output = WaybarOutput::parse(
+            r#"{"text": "42%", "alt": "muted", "tooltip": "Speakers\nDefault sink", "class": ["warning", "muted"], "percentage": 42}"#,
+        )
+        .unwrap();
+        assert_eq!(output.text, "42%");
+        assert_eq!(output.alt.as_deref(), Some("muted"));
+        assert_eq!(output.tooltip.as_deref(), Some("Speakers\nDefault sink"));
+        assert_eq!(output.class, vec!["warning", "muted"]);
+        assert_eq!(output.percentage, Some(42.0));
+
+        let output = WaybarOutput::parse(r#"{"text": "up", "class": "good"}"#).unwrap();
+        assert_eq!(output.class, vec!["good"]);
+        assert_eq!(output.percentage, None);
+        assert!(WaybarOutput::parse("plain text").is_err());
+
+        let icons = ["a", "b", "c", "d"];
+        assert_eq!(ramp(&icons, 0.0), Some(&"a"));
+        assert_eq!(ramp(&icons, 49.0), Some(&"b"));
+        assert_eq!(ramp(&icons, 100.0), Some(&"d"));
+        assert_eq!(ramp::<&str>(&[], 50.0), None);
+    }
+}
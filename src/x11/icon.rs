@@ -0,0 +1,106 @@
+use xcb::x;
+
+use crate::error::MyResult;
+
+/// 一个 _NET_WM_ICON 图标，像素为未预乘 alpha 的 ARGB
+pub struct Icon {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+}
+
+/// 读取窗口的 _NET_WM_ICON，选出最适合 size 的一个
+pub fn get_wm_icon(
+    conn: &xcb::Connection,
+    atom: x::Atom,
+    window: x::Window,
+    size: u32,
+) -> MyResult<Option<Icon>> {
+    let reply = conn.wait_for_reply(conn.send_request(&x::GetProperty {
+        delete: false,
+        window,
+        property: atom,
+        r#type: x::ATOM_CARDINAL,
+        long_offset: 0,
+        long_length: u32::MAX,
+    }))?;
+    if reply.format() != 32 {
+        return Ok(None);
+    }
+    Ok(pick_icon(reply.value::<u32>(), size))
+}
+
+/// 读取 WM_CLASS，返回 (instance, class)
+pub fn get_wm_class(
+    conn: &xcb::Connection,
+    window: x::Window,
+) -> MyResult<Option<(String, String)>> {
+    let reply = conn.wait_for_reply(conn.send_request(&x::GetProperty {
+        delete: false,
+        window,
+        property: x::ATOM_WM_CLASS,
+        r#type: x::ATOM_STRING,
+        long_offset: 0,
+        long_length: 1024,
+    }))?;
+    let mut parts = reply
+        .value::<u8>()
+        .split(|b| *b == 0)
+        .map(|s| String::from_utf8_lossy(s).into_owned());
+    match (parts.next(), parts.next()) {
+        (Some(instance), Some(class)) => Ok(Some((instance, class))),
+        _ => Ok(None),
+    }
+}
+
+/// _NET_WM_ICON 是若干个 [width, height, width * height 个像素] 连在一起。
+/// 优先选不小于 size 的最小图标（缩小比放大清楚），都比 size 小时选最大的。
+pub fn pick_icon(data: &[u32], size: u32) -> Option<Icon> {
+    let mut best: Option<(u32, u32, &[u32])> = None;
+    let mut rest = data;
+    while rest.len() >= 2 {
+        let (w, h) = (rest[0], rest[1]);
+        let len = (w as usize).saturating_mul(h as usize);
+        if w == 0 || h == 0 || rest.len() - 2 < len {
+            break;
+        }
+        let pixels = &rest[2..2 + len];
+        rest = &rest[2 + len..];
+
+        let better = match best {
+            None => true,
+            Some((bw, _, _)) if bw >= size => w >= size && w < bw,
+            Some((bw, _, _)) => w > bw,
+        };
+        if better {
+            best = Some((w, h, pixels));
+        }
+    }
+    best.map(|(width, height, pixels)| Icon {
+        width,
+        height,
+        pixels: pixels.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::pick_icon;
+
+    fn icon(size: u32) -> Vec<u32> {
+        let mut v = vec![size, size];
+        v.extend(std::iter::repeat_n(size, (size * size) as usize));
+        v
+    }
+
+    #[test]
+    fn pick_best_size() {
+        let data = [icon(16), icon(48), icon(32), icon(128)].concat();
+        assert_eq!(pick_icon(&data, 24).unwrap().width, 32);
+        assert_eq!(pick_icon(&data, 200).unwrap().width, 128);
+        assert_eq!(pick_icon(&data, 16).unwrap().width, 16);
+        // 数据被截断时只用完整的部分
+        assert_eq!(pick_icon(&data[..300], 24).unwrap().width, 16);
+        assert!(pick_icon(&[], 24).is_none());
+    }
+}
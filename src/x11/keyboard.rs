@@ -0,0 +1,25 @@
+use xcb::x;
+
+use crate::error::MyResult;
+
+pub const XK_ESCAPE: x::Keysym = 0xff1b;
+
+/// 当前键盘映射里产生 keysym 的所有 keycode
+///
+/// keycode 和按键的对应关系取决于驱动和键盘布局，不能写死。
+pub fn keycodes(conn: &xcb::Connection, keysym: x::Keysym) -> MyResult<Vec<x::Keycode>> {
+    let setup = conn.get_setup();
+    let first = setup.min_keycode();
+    let reply = conn.wait_for_reply(conn.send_request(&x::GetKeyboardMapping {
+        first_keycode: first,
+        count: setup.max_keycode() - first + 1,
+    }))?;
+    let per_keycode = (reply.keysyms_per_keycode() as usize).max(1);
+    Ok(reply
+        .keysyms()
+        .chunks(per_keycode)
+        .enumerate()
+        .filter(|(_, keysyms)| keysyms.contains(&keysym))
+        .map(|(i, _)| first + i as x::Keycode)
+        .collect())
+}
@@ -1,5 +1,11 @@
 mod window;
 mod ewmh;
+pub mod icon;
+mod keyboard;
+mod text;
+mod tracker;
 
-pub use window::create_window;
-pub use ewmh::setup_ewmh; 
\ No newline at end of file
+pub use window::{create_popup, create_window, popup_geometry};
+pub use ewmh::setup_ewmh;
+pub use keyboard::{XK_ESCAPE, keycodes};
+pub use tracker::WindowTracker;
\ No newline at end of file
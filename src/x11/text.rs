@@ -0,0 +1,82 @@
+use std::ffi::{CStr, c_char, c_int};
+use std::ptr;
+
+use ::x11::xlib;
+use xcb::{Xid, x};
+
+/// 文本属性的编码，由属性的类型决定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextEncoding {
+    Utf8,
+    /// ICCCM 的 STRING 类型是 Latin-1
+    Latin1,
+}
+
+/// 按编码把属性值解码成字符串，末尾的 \0 会被去掉
+pub fn decode_text(bytes: &[u8], encoding: TextEncoding) -> String {
+    let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+    match encoding {
+        TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        TextEncoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// 只用来转换 COMPOUND_TEXT 的 Xlib 连接
+///
+/// COMPOUND_TEXT 可以在 GB2312、JIS X 0208、KS C 5601 等字符集之间来回切换，
+/// 交给 Xlib 的 Xutf8TextPropertyToTextList 转成 UTF-8。
+pub struct CompoundText {
+    display: *mut xlib::Display,
+}
+
+impl CompoundText {
+    /// 连接 DISPLAY 指定的服务器，和 xcb 连接是同一个服务器，atom 可以通用
+    pub fn open() -> Option<Self> {
+        let display = unsafe { xlib::XOpenDisplay(ptr::null()) };
+        (!display.is_null()).then_some(Self { display })
+    }
+
+    /// encoding 是属性的类型（COMPOUND_TEXT 的 atom），无法转换时返回 None
+    pub fn decode(&self, bytes: &[u8], encoding: x::Atom) -> Option<String> {
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        let property = xlib::XTextProperty {
+            value: bytes.as_ptr() as *mut u8,
+            encoding: encoding.resource_id() as xlib::Atom,
+            format: 8,
+            nitems: bytes.len() as _,
+        };
+        let mut list: *mut *mut c_char = ptr::null_mut();
+        let mut count: c_int = 0;
+        // 返回负数表示失败，正数是无法转换的字符个数（这些字符被替换成默认字符）
+        let status = unsafe {
+            xlib::Xutf8TextPropertyToTextList(self.display, &property, &mut list, &mut count)
+        };
+        if status < 0 || list.is_null() {
+            return None;
+        }
+        // 以 \0 分隔的多个字符串会转换成多项
+        let text = (0..count.max(0) as usize)
+            .map(|i| unsafe { CStr::from_ptr(*list.add(i)) }.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("");
+        unsafe { xlib::XFreeStringList(list) };
+        Some(text)
+    }
+}
+
+impl Drop for CompoundText {
+    fn drop(&mut self) {
+        unsafe { xlib::XCloseDisplay(self.display) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TextEncoding, decode_text};
+
+    #[test]
+    fn decode_latin1_and_utf8() {
+        assert_eq!(decode_text(b"caf\xe9\0", TextEncoding::Latin1), "café");
+        assert_eq!(decode_text("终端".as_bytes(), TextEncoding::Utf8), "终端");
+    }
+}
@@ -0,0 +1,259 @@
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashSet;
+
+use xcb::{Xid, x};
+use xcb_wm::ewmh;
+
+use super::text::{CompoundText, TextEncoding, decode_text};
+use crate::error::MyResult;
+
+// 在客户端窗口上订阅的事件
+const CLIENT_EVENTS: x::EventMask =
+    x::EventMask::PROPERTY_CHANGE.union(x::EventMask::STRUCTURE_NOTIFY);
+
+/// 活动窗口的变化
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrackerEvent {
+    /// _NET_ACTIVE_WINDOW 变了，None 表示没有聚焦的窗口
+    ActiveChanged(Option<x::Window>),
+    /// 活动窗口的 _NET_WM_NAME 或 WM_NAME 变了
+    TitleChanged(x::Window),
+    /// 活动窗口被销毁
+    Destroyed(x::Window),
+    /// 当前桌面或桌面名称变了
+    DesktopChanged,
+}
+
+/// 跟踪当前活动窗口
+///
+/// 在根窗口上监听 _NET_ACTIVE_WINDOW，并把 PROPERTY_CHANGE 订阅转移到当前活动窗口上，
+/// 这样标题变化和窗口销毁都能收到事件。
+///
+/// 同一个连接在一个窗口上只有一个事件掩码，所以其它需要监听客户端窗口属性的组件（例如任务栏）
+/// 也要通过 [`WindowTracker::watch`] 订阅，避免切换活动窗口时把它们的订阅取消掉。
+pub struct WindowTracker<'a> {
+    conn: &'a xcb::Connection,
+    ewmh: &'a ewmh::Connection<'a>,
+    root: x::Window,
+    compound_text: x::Atom,
+    // 第一次遇到 COMPOUND_TEXT 的标题时才打开
+    xlib: OnceCell<Option<CompoundText>>,
+    active: Cell<Option<x::Window>>,
+    watched: RefCell<HashSet<x::Window>>,
+}
+
+impl<'a> WindowTracker<'a> {
+    pub fn new(
+        conn: &'a xcb::Connection,
+        ewmh: &'a ewmh::Connection<'a>,
+        root: x::Window,
+    ) -> MyResult<Self> {
+        conn.check_request(conn.send_request_checked(&x::ChangeWindowAttributes {
+            window: root,
+            value_list: &[x::Cw::EventMask(x::EventMask::PROPERTY_CHANGE)],
+        }))?;
+        let compound_text = conn
+            .wait_for_reply(conn.send_request(&x::InternAtom {
+                only_if_exists: false,
+                name: b"COMPOUND_TEXT",
+            }))?
+            .atom();
+        let tracker = Self {
+            conn,
+            ewmh,
+            root,
+            compound_text,
+            xlib: OnceCell::new(),
+            active: Cell::new(None),
+            watched: RefCell::new(HashSet::new()),
+        };
+        tracker.retarget(tracker.query_active());
+        Ok(tracker)
+    }
+
+    pub fn conn(&self) -> &'a xcb::Connection {
+        self.conn
+    }
+
+    pub fn ewmh(&self) -> &'a ewmh::Connection<'a> {
+        self.ewmh
+    }
+
+    pub fn root(&self) -> x::Window {
+        self.root
+    }
+
+    pub fn active(&self) -> Option<x::Window> {
+        self.active.get()
+    }
+
+    // 不用 ewmh::proto::GetActiveWindow：属性不存在时它的回复解析会 panic
+    fn query_active(&self) -> Option<x::Window> {
+        let reply = self.get_property(self.root, self.ewmh.atoms._NET_ACTIVE_WINDOW, 32);
+        match reply {
+            Ok(reply) => reply
+                .and_then(|r| r.value::<x::Window>().first().copied())
+                .filter(|w| w.resource_id() != 0),
+            Err(e) => {
+                eprintln!("get _NET_ACTIVE_WINDOW: {e}");
+                None
+            }
+        }
+    }
+
+    // 取消旧窗口上的订阅，订阅新窗口；窗口可能已经被销毁，所以错误只打印不返回
+    fn retarget(&self, window: Option<x::Window>) {
+        let old = self.active.replace(window);
+        if old == window {
+            return;
+        }
+        let watched = self.watched.borrow();
+        if let Some(old) = old.filter(|w| !watched.contains(w)) {
+            self.select(old, x::EventMask::NO_EVENT);
+        }
+        if let Some(window) = window.filter(|w| !watched.contains(w)) {
+            self.select(window, CLIENT_EVENTS);
+        }
+    }
+
+    /// 订阅一组客户端窗口的属性变化和销毁事件，替换之前 watch 的那一组
+    pub fn watch(&self, windows: &[x::Window]) {
+        let windows: HashSet<x::Window> = windows.iter().copied().collect();
+        let mut watched = self.watched.borrow_mut();
+        let active = self.active.get();
+        for &window in watched.difference(&windows) {
+            if Some(window) != active {
+                self.select(window, x::EventMask::NO_EVENT);
+            }
+        }
+        for &window in windows.difference(&watched) {
+            if Some(window) != active {
+                self.select(window, CLIENT_EVENTS);
+            }
+        }
+        *watched = windows;
+    }
+
+    fn select(&self, window: x::Window, mask: x::EventMask) {
+        let cookie = self.conn.send_request_checked(&x::ChangeWindowAttributes {
+            window,
+            value_list: &[x::Cw::EventMask(mask)],
+        });
+        if let Err(e) = self.conn.check_request(cookie) {
+            eprintln!("select events on {window:?}: {e}");
+        }
+    }
+
+    /// 主循环把每个事件先交给 tracker，返回活动窗口相关的变化
+    pub fn handle_event(&self, event: &xcb::Event) -> Option<TrackerEvent> {
+        match event {
+            xcb::Event::X(x::Event::PropertyNotify(ev)) => {
+                if ev.window() == self.root && ev.atom() == self.ewmh.atoms._NET_ACTIVE_WINDOW {
+                    let active = self.query_active();
+                    if active == self.active.get() {
+                        return None;
+                    }
+                    self.retarget(active);
+                    return Some(TrackerEvent::ActiveChanged(active));
+                }
+                if ev.window() == self.root
+                    && (ev.atom() == self.ewmh.atoms._NET_CURRENT_DESKTOP
+                        || ev.atom() == self.ewmh.atoms._NET_DESKTOP_NAMES)
+                {
+                    return Some(TrackerEvent::DesktopChanged);
+                }
+                let is_name =
+                    ev.atom() == self.ewmh.atoms._NET_WM_NAME || ev.atom() == x::ATOM_WM_NAME;
+                if is_name && Some(ev.window()) == self.active.get() {
+                    return Some(TrackerEvent::TitleChanged(ev.window()));
+                }
+                None
+            }
+            xcb::Event::X(x::Event::DestroyNotify(ev)) => {
+                // 窗口已经不存在，不需要取消订阅
+                self.watched.borrow_mut().remove(&ev.window());
+                if Some(ev.window()) != self.active.get() {
+                    return None;
+                }
+                self.active.set(None);
+                Some(TrackerEvent::Destroyed(ev.window()))
+            }
+            _ => None,
+        }
+    }
+
+    /// 读取窗口标题：优先 _NET_WM_NAME，没有时退回 ICCCM 的 WM_NAME
+    ///
+    /// WM_NAME 按属性类型解码，可能是 STRING（Latin-1）、COMPOUND_TEXT 或 UTF8_STRING。
+    pub fn window_name(&self, window: x::Window) -> MyResult<Option<String>> {
+        for property in [self.ewmh.atoms._NET_WM_NAME, x::ATOM_WM_NAME] {
+            let Some(reply) = self.get_property(window, property, 8)? else {
+                continue;
+            };
+            let bytes = reply.value::<u8>();
+            if bytes.is_empty() {
+                continue;
+            }
+            let text = if reply.r#type() == self.compound_text {
+                self.decode_compound_text(bytes)
+            } else if reply.r#type() == x::ATOM_STRING {
+                decode_text(bytes, TextEncoding::Latin1)
+            } else {
+                decode_text(bytes, TextEncoding::Utf8)
+            };
+            return Ok(Some(text));
+        }
+        Ok(None)
+    }
+
+    fn decode_compound_text(&self, bytes: &[u8]) -> String {
+        let xlib = self.xlib.get_or_init(|| {
+            let xlib = CompoundText::open();
+            if xlib.is_none() {
+                eprintln!("cannot open Xlib display, COMPOUND_TEXT is decoded as Latin-1");
+            }
+            xlib
+        });
+        xlib.as_ref()
+            .and_then(|xlib| xlib.decode(bytes, self.compound_text))
+            // 至少 ASCII 部分是对的
+            .unwrap_or_else(|| decode_text(bytes, TextEncoding::Latin1))
+    }
+
+    /// 当前桌面的名称
+    pub fn desktop_name(&self) -> MyResult<Option<String>> {
+        let current = self.get_property(self.root, self.ewmh.atoms._NET_CURRENT_DESKTOP, 32)?;
+        let Some(&index) = current.as_ref().and_then(|r| r.value::<u32>().first()) else {
+            return Ok(None);
+        };
+        let names = self.get_property(self.root, self.ewmh.atoms._NET_DESKTOP_NAMES, 8)?;
+        let name = names.and_then(|names| {
+            names
+                .value::<u8>()
+                .split(|b| *b == 0)
+                .nth(index as usize)
+                .map(|name| decode_text(name, TextEncoding::Utf8))
+        });
+        Ok(name)
+    }
+
+    /// 读取窗口属性，格式不符（包括属性不存在）时返回 None
+    pub fn get_property(
+        &self,
+        window: x::Window,
+        property: x::Atom,
+        format: u8,
+    ) -> MyResult<Option<x::GetPropertyReply>> {
+        let reply = self
+            .conn
+            .wait_for_reply(self.conn.send_request(&x::GetProperty {
+                delete: false,
+                window,
+                property,
+                r#type: x::ATOM_ANY,
+                long_offset: 0,
+                long_length: 1024,
+            }))?;
+        Ok(Some(reply).filter(|r| r.format() == format))
+    }
+}
@@ -1,5 +1,7 @@
 use xcb::x;
 
+use crate::error::MyResult;
+
 pub fn create_window(conn: &xcb::Connection, screen: &x::Screen) -> (x::Window, x::Visualtype) {
     let wid = conn.generate_id();
 
@@ -49,7 +51,10 @@ pub fn create_window(conn: &xcb::Connection, screen: &x::Screen) -> (x::Window,
                 x::EventMask::EXPOSURE
                     | x::EventMask::BUTTON_PRESS
                     | x::EventMask::KEY_PRESS
-                    | x::EventMask::PROPERTY_CHANGE,
+                    | x::EventMask::PROPERTY_CHANGE
+                    // 用于鼠标悬停提示
+                    | x::EventMask::POINTER_MOTION
+                    | x::EventMask::LEAVE_WINDOW,
             ),
             x::Cw::Colormap(colormap),
         ],
@@ -65,3 +70,67 @@ pub fn create_window(conn: &xcb::Connection, screen: &x::Screen) -> (x::Window,
 
     (wid, *visual)
 }
+
+/// 计算贴着状态栏、从 anchor_x 处弹出的窗口位置，返回根窗口和几何信息
+///
+/// 状态栏在屏幕下半部分时弹在上方，否则弹在下方；水平方向不超出屏幕。
+pub fn popup_geometry(
+    conn: &xcb::Connection,
+    bar: x::Window,
+    anchor_x: i16,
+    width: u16,
+    height: u16,
+) -> MyResult<(x::Window, (i16, i16, u16, u16))> {
+    let bar_geometry = conn.wait_for_reply(conn.send_request(&x::GetGeometry {
+        drawable: x::Drawable::Window(bar),
+    }))?;
+    let root = bar_geometry.root();
+    let screen = conn.wait_for_reply(conn.send_request(&x::GetGeometry {
+        drawable: x::Drawable::Window(root),
+    }))?;
+    let y = if bar_geometry.y() as i32 > screen.height() as i32 / 2 {
+        bar_geometry.y() - height as i16
+    } else {
+        bar_geometry.y() + bar_geometry.height() as i16
+    };
+    let x = anchor_x.clamp(0, (screen.width() as i16 - width as i16).max(0));
+    Ok((root, (x, y, width, height)))
+}
+
+/// 创建一个不受窗口管理器管理的弹出窗口（override redirect）
+///
+/// 视觉和 colormap 与状态栏相同，都是 32 位的，这样才能和状态栏一样支持透明。
+pub fn create_popup(
+    conn: &xcb::Connection,
+    bar: x::Window,
+    root: x::Window,
+    visual: &x::Visualtype,
+    geometry: (i16, i16, u16, u16),
+) -> MyResult<x::Window> {
+    let attrs = conn.wait_for_reply(conn.send_request(&x::GetWindowAttributes { window: bar }))?;
+    let (x, y, width, height) = geometry;
+
+    let wid = conn.generate_id();
+    conn.check_request(conn.send_request_checked(&x::CreateWindow {
+        depth: 32,
+        wid,
+        parent: root,
+        x,
+        y,
+        width,
+        height,
+        border_width: 0,
+        class: x::WindowClass::InputOutput,
+        visual: visual.visual_id(),
+        value_list: &[
+            x::Cw::BackPixel(0x0),
+            x::Cw::BorderPixel(0x0),
+            x::Cw::OverrideRedirect(true),
+            x::Cw::EventMask(
+                x::EventMask::EXPOSURE | x::EventMask::BUTTON_PRESS | x::EventMask::KEY_PRESS,
+            ),
+            x::Cw::Colormap(attrs.colormap()),
+        ],
+    }))?;
+    Ok(wid)
+}